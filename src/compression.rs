@@ -0,0 +1,84 @@
+use std::io::{
+    self,
+    BufRead,
+};
+
+
+/// Compression formats. Input format detected by magic bytes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Xz,
+    Bzip2,
+    Zstd,
+    Zip,
+}
+
+
+static MAGIC: &[(&[u8], Compression)] = &[
+    (&[0x1f, 0x8b], Compression::Gzip),
+    (&[0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00], Compression::Xz),
+    (b"BZh", Compression::Bzip2),
+    (&[0x28, 0xb5, 0x2f, 0xfd], Compression::Zstd),
+    (b"PK\x03\x04", Compression::Zip),
+];
+
+
+impl Compression {
+    /// Detects compression format without consuming data from the source
+    pub fn detect<R: BufRead>(src: &mut R) -> io::Result<Compression> {
+        let buf = src.fill_buf()?;
+
+        for (magic, compression) in MAGIC {
+            if buf.starts_with(magic) {
+                return Ok(*compression);
+            }
+        }
+
+        Ok(Compression::None)
+    }
+
+    /// Detects compression by the file name extension
+    pub fn from_path(path: &str) -> Compression {
+        let path = path.to_lowercase();
+        if path.ends_with(".gz") {
+            Compression::Gzip
+        } else if path.ends_with(".xz") {
+            Compression::Xz
+        } else if path.ends_with(".bz2") {
+            Compression::Bzip2
+        } else if path.ends_with(".zst") {
+            Compression::Zstd
+        } else if path.ends_with(".zip") {
+            Compression::Zip
+        } else {
+            Compression::None
+        }
+    }
+
+    /// Strips compression extension from the file name
+    #[cfg_attr(not(feature = "json"), allow(dead_code))]
+    pub (crate) fn strip_extension(self, path: &str) -> &str {
+        let ext = match self {
+            Compression::None => return path,
+            Compression::Gzip => 3,
+            Compression::Xz => 3,
+            Compression::Bzip2 => 4,
+            Compression::Zstd => 4,
+            Compression::Zip => 4,
+        };
+        &path[.. path.len() - ext]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Compression::None => "none",
+            Compression::Gzip => "gzip",
+            Compression::Xz => "xz",
+            Compression::Bzip2 => "bzip2",
+            Compression::Zstd => "zstd",
+            Compression::Zip => "zip",
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! DVB time encoding (ETSI EN 300 468 Annex C).
+//! Event start is 16 bit Modified Julian Date and UTC time in 6 BCD digits,
+//! event duration is 6 BCD digits: hh:mm:ss up to 99:59:59
+
+
+/// MJD of 1970-01-01
+const MJD_UNIX_EPOCH: i64 = 40587;
+/// MJD of 1900-03-01. Annex C conversion is valid from this date
+const MJD_MIN: i64 = 15079;
+/// Maximum MJD in 16 bits: 2038-04-22
+const MJD_MAX: i64 = 0xFFFF;
+
+/// Minimum UNIX time encoded in DVB start time: 1900-03-01 00:00:00
+pub const DVB_TIME_MIN: i64 = (MJD_MIN - MJD_UNIX_EPOCH) * 86400;
+/// Maximum UNIX time encoded in DVB start time: 2038-04-22 23:59:59
+pub const DVB_TIME_MAX: i64 = (MJD_MAX - MJD_UNIX_EPOCH + 1) * 86400 - 1;
+/// Maximum duration in BCD: 99:59:59
+pub const DVB_DURATION_MAX: i64 = 99 * 3600 + 59 * 60 + 59;
+
+
+#[derive(Debug, Error)]
+pub enum DvbTimeError {
+    #[error_kind("DvbTime: time out of MJD range: {}", 0)]
+    TimeOutOfRange(i64),
+    #[error_kind("DvbTime: duration out of range: {}", 0)]
+    DurationOutOfRange(i64),
+    #[error_kind("DvbTime: invalid BCD value")]
+    InvalidBcd,
+}
+
+
+type Result<T> = std::result::Result<T, DvbTimeError>;
+
+
+#[inline]
+fn to_bcd(value: i64) -> u8 {
+    (((value / 10) << 4) | (value % 10)) as u8
+}
+
+
+#[inline]
+fn from_bcd(value: u8, max: i64) -> Result<i64> {
+    let hi = i64::from(value >> 4);
+    let lo = i64::from(value & 0x0F);
+    if hi > 9 || lo > 9 {
+        return Err(DvbTimeError::InvalidBcd);
+    }
+
+    let value = hi * 10 + lo;
+    if value > max {
+        return Err(DvbTimeError::InvalidBcd);
+    }
+
+    Ok(value)
+}
+
+
+fn encode_hms(seconds: i64) -> [u8; 3] {
+    [
+        to_bcd(seconds / 3600),
+        to_bcd(seconds / 60 % 60),
+        to_bcd(seconds % 60),
+    ]
+}
+
+
+fn decode_hms(data: &[u8], max_hours: i64) -> Result<i64> {
+    let h = from_bcd(data[0], max_hours)?;
+    let m = from_bcd(data[1], 59)?;
+    let s = from_bcd(data[2], 59)?;
+    Ok(h * 3600 + m * 60 + s)
+}
+
+
+/// Checks that UNIX time could be encoded as DVB start time
+pub fn check_time(time: i64) -> Result<()> {
+    if ! (DVB_TIME_MIN ..= DVB_TIME_MAX).contains(&time) {
+        Err(DvbTimeError::TimeOutOfRange(time))
+    } else {
+        Ok(())
+    }
+}
+
+
+/// Checks that duration could be encoded in BCD
+pub fn check_duration(duration: i64) -> Result<()> {
+    if ! (0 ..= DVB_DURATION_MAX).contains(&duration) {
+        Err(DvbTimeError::DurationOutOfRange(duration))
+    } else {
+        Ok(())
+    }
+}
+
+
+/// Returns Modified Julian Date for UNIX time
+pub fn time_to_mjd(time: i64) -> Result<u16> {
+    check_time(time)?;
+    Ok((time.div_euclid(86400) + MJD_UNIX_EPOCH) as u16)
+}
+
+
+/// Returns UNIX time for the midnight of Modified Julian Date
+pub fn mjd_to_time(mjd: u16) -> Result<i64> {
+    let mjd = i64::from(mjd);
+    if mjd < MJD_MIN {
+        return Err(DvbTimeError::TimeOutOfRange((mjd - MJD_UNIX_EPOCH) * 86400));
+    }
+    Ok((mjd - MJD_UNIX_EPOCH) * 86400)
+}
+
+
+/// Encodes UNIX time to DVB start time: MJD and UTC in BCD
+pub fn encode_time(time: i64) -> Result<[u8; 5]> {
+    let mjd = time_to_mjd(time)?;
+    let hms = encode_hms(time.rem_euclid(86400));
+    Ok([(mjd >> 8) as u8, mjd as u8, hms[0], hms[1], hms[2]])
+}
+
+
+/// Decodes DVB start time to UNIX time
+pub fn decode_time(data: &[u8; 5]) -> Result<i64> {
+    let mjd = (u16::from(data[0]) << 8) | u16::from(data[1]);
+    Ok(mjd_to_time(mjd)? + decode_hms(&data[2 ..], 23)?)
+}
+
+
+/// Encodes duration in seconds to BCD hh:mm:ss
+pub fn encode_duration(duration: i64) -> Result<[u8; 3]> {
+    check_duration(duration)?;
+    Ok(encode_hms(duration))
+}
+
+
+/// Decodes BCD hh:mm:ss duration to seconds
+pub fn decode_duration(data: &[u8; 3]) -> Result<i64> {
+    decode_hms(data, 99)
+}
@@ -0,0 +1,240 @@
+use std::collections::VecDeque;
+
+use crate::{
+    eit_generator::{
+        SEGMENT,
+        SEGMENT_SECTIONS,
+        TABLE_SEGMENTS,
+    },
+    EitGenerator,
+    EitSection,
+    Epg,
+    EIT_PF_ACTUAL,
+    EIT_PF_OTHER,
+    EIT_SCHEDULE_OTHER,
+};
+
+
+/// EIT PID
+pub const EIT_PID: u16 = 0x12;
+
+const PACKET_SIZE: usize = 188;
+
+/// Queues in order of priority
+const PF: usize = 0;
+const PF_OTHER: usize = 1;
+const SCHEDULE: usize = 2;
+const SCHEDULE_OTHER: usize = 3;
+const SCHEDULE_FAR: usize = 4;
+const SCHEDULE_OTHER_FAR: usize = 5;
+const QUEUES: usize = 6;
+
+
+/// EIT carousel. Generates EIT for all channels mapped in `Epg::services`
+/// and packetizes it to PID 0x12.
+/// Default repetition intervals are minimum rates for satellite and cable
+/// networks by ETSI TR 101 211: present/following for the actual transport
+/// stream every 2 seconds and for other every 10 seconds, schedule for the
+/// first day every 10 seconds and for the next days every 30 seconds.
+/// Schedule day defined by the segment of the section.
+/// Output limited by the bitrate, present/following sent first.
+/// Services of other transport streams sent if `generator.actual` is defined
+#[derive(Debug)]
+pub struct EitCarousel {
+    /// Output bitrate in bits per second
+    pub bitrate: u64,
+    /// Present/following repetition interval in milliseconds
+    pub pf_interval: u64,
+    /// Present/following repetition interval for other transport streams in milliseconds
+    pub pf_other_interval: u64,
+    /// Schedule repetition interval for the first day in milliseconds
+    pub schedule_interval: u64,
+    /// Schedule repetition interval for the next days in milliseconds
+    pub schedule_far_interval: u64,
+    /// Schedule repetition interval for the first day of other transport streams in milliseconds
+    pub schedule_other_interval: u64,
+    /// Schedule repetition interval for the next days of other transport streams in milliseconds
+    pub schedule_other_far_interval: u64,
+    pub generator: EitGenerator,
+    /// Continuity counter
+    cc: u8,
+    /// Time of the previous poll
+    last: Option<i64>,
+    /// Bits allowed to send
+    budget: u64,
+    /// Next cycle time of each queue
+    next: [i64; QUEUES],
+    queues: [VecDeque<[u8; PACKET_SIZE]>; QUEUES],
+}
+
+
+impl Default for EitCarousel {
+    fn default() -> Self {
+        EitCarousel {
+            bitrate: 1_000_000,
+            pf_interval: 2_000,
+            pf_other_interval: 10_000,
+            schedule_interval: 10_000,
+            schedule_far_interval: 30_000,
+            schedule_other_interval: 10_000,
+            schedule_other_far_interval: 30_000,
+            generator: EitGenerator::default(),
+            cc: 0,
+            last: None,
+            budget: 0,
+            next: [i64::MIN; QUEUES],
+            queues: Default::default(),
+        }
+    }
+}
+
+
+/// Returns queue for the section: by table_id and day of the schedule segment
+fn queue(section: &EitSection) -> usize {
+    let table_id = section.eit.table_id;
+    if table_id == EIT_PF_ACTUAL || table_id == EIT_PF_OTHER {
+        return if table_id == EIT_PF_ACTUAL { PF } else { PF_OTHER };
+    }
+
+    let other = table_id >= EIT_SCHEDULE_OTHER;
+    /* table_id 0x50-0x5F or 0x60-0x6F: table number in the low bits */
+    let segment = usize::from(table_id & 0x0F) * TABLE_SEGMENTS
+        + usize::from(section.section_number) / SEGMENT_SECTIONS;
+    let first_day = (segment as i64) * SEGMENT < 86400;
+
+    match (other, first_day) {
+        (false, true) => SCHEDULE,
+        (true, true) => SCHEDULE_OTHER,
+        (false, false) => SCHEDULE_FAR,
+        (true, false) => SCHEDULE_OTHER_FAR,
+    }
+}
+
+
+/// Drops queued sections not started yet.
+/// Rest of the section in progress is kept to keep it complete
+fn truncate(queue: &mut VecDeque<[u8; PACKET_SIZE]>) {
+    /* section starts in the packet with payload_unit_start_indicator */
+    let keep = queue.iter()
+        .position(|packet| packet[1] & 0x40 != 0)
+        .unwrap_or(queue.len());
+    queue.truncate(keep);
+}
+
+
+/// Appends section packets to the queue
+fn packetize(section: &EitSection, queue: &mut VecDeque<[u8; PACKET_SIZE]>) {
+    let mut cc = 0;
+    let mut data = Vec::new();
+    /* section with events above the size limit not sent */
+    if section.demux(EIT_PID, &mut cc, &mut data).is_err() {
+        return;
+    }
+
+    for chunk in data.chunks_exact(PACKET_SIZE) {
+        let mut packet = [0; PACKET_SIZE];
+        packet.copy_from_slice(chunk);
+        queue.push_back(packet);
+    }
+}
+
+
+impl EitCarousel {
+    pub fn new(bitrate: u64) -> Self {
+        EitCarousel {
+            bitrate,
+            ..Default::default()
+        }
+    }
+
+    /// Generates tables with expired repetition interval.
+    /// Sections not sent since previous cycle replaced with new ones,
+    /// section in progress sent till the end
+    fn cycle(&mut self, epg: &Epg, time: i64) {
+        let intervals = [
+            self.pf_interval,
+            self.pf_other_interval,
+            self.schedule_interval,
+            self.schedule_other_interval,
+            self.schedule_far_interval,
+            self.schedule_other_far_interval,
+        ];
+
+        let mut expired = [false; QUEUES];
+        for (i, interval) in intervals.iter().enumerate() {
+            if time >= self.next[i] {
+                expired[i] = true;
+                truncate(&mut self.queues[i]);
+                self.next[i] = time.saturating_add(*interval as i64);
+            }
+        }
+
+        let pf = expired[PF] || expired[PF_OTHER];
+        let schedule = expired[SCHEDULE ..].iter().any(|v| *v);
+        if ! (pf || schedule) {
+            return;
+        }
+
+        let unix_time = time.div_euclid(1000);
+
+        for (id, _) in epg.services.iter() {
+            let mut sections = Vec::new();
+            if pf {
+                sections.append(&mut self.generator.present_following(epg, id, unix_time).unwrap_or_default());
+            }
+            if schedule {
+                sections.append(&mut self.generator.schedule(epg, id, unix_time));
+            }
+
+            for section in &sections {
+                let i = queue(section);
+                if expired[i] {
+                    packetize(section, &mut self.queues[i]);
+                }
+            }
+        }
+    }
+
+    /// Returns next packet. Section in progress sent till the end,
+    /// next section selected by the queue priority
+    fn next_packet(&mut self) -> Option<[u8; PACKET_SIZE]> {
+        let queues = &mut self.queues;
+
+        let i = queues.iter()
+            .position(|queue| matches!(queue.front(), Some(packet) if packet[1] & 0x40 == 0))
+            .or_else(|| queues.iter().position(|queue| ! queue.is_empty()))?;
+
+        queues[i].pop_front()
+    }
+
+    /// Appends TS packets to `dst` for the time interval since previous call.
+    /// `time` is a current UNIX time in milliseconds
+    pub fn poll(&mut self, epg: &Epg, time: i64, dst: &mut Vec<u8>) {
+        self.cycle(epg, time);
+
+        let elapsed = self.last.map(|v| time.saturating_sub(v).max(0) as u64).unwrap_or(0);
+        self.last = Some(time);
+
+        /* unused budget limited to one second */
+        self.budget = (self.budget + self.bitrate * elapsed / 1000).min(self.bitrate);
+
+        let packet_bits = (PACKET_SIZE * 8) as u64;
+        while self.budget >= packet_bits {
+            let mut packet = match self.next_packet() {
+                Some(v) => v,
+                None => break,
+            };
+
+            packet[3] = (packet[3] & 0xF0) | self.cc;
+            self.cc = (self.cc + 1) & 0x0F;
+
+            dst.extend_from_slice(&packet);
+            self.budget -= packet_bits;
+        }
+    }
+
+    /// Returns number of packets waiting for the budget
+    pub fn pending(&self) -> usize {
+        self.queues.iter().map(VecDeque::len).sum()
+    }
+}
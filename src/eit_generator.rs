@@ -0,0 +1,280 @@
+use std::collections::HashMap;
+
+use mpegts::psi::{
+    Eit,
+    EitItem,
+};
+
+use crate::{
+    DvbService,
+    EitError,
+    EitSection,
+    Epg,
+    EpgEvent,
+};
+
+
+/// EIT present/following table for actual transport stream
+pub const EIT_PF_ACTUAL: u8 = 0x4E;
+/// First EIT schedule table for actual transport stream
+pub const EIT_SCHEDULE_ACTUAL: u8 = 0x50;
+/// EIT present/following table for other transport stream
+pub const EIT_PF_OTHER: u8 = 0x4F;
+/// First EIT schedule table for other transport stream
+pub const EIT_SCHEDULE_OTHER: u8 = 0x60;
+
+/// Schedule segment duration. 3 hours
+pub (crate) const SEGMENT: i64 = 3 * 3600;
+/// Sections in the schedule segment
+pub (crate) const SEGMENT_SECTIONS: usize = 8;
+/// Segments in the schedule table. 4 days
+pub (crate) const TABLE_SEGMENTS: usize = 32;
+/// Schedule window. 8 days
+const SCHEDULE_DAYS: i64 = 8;
+
+
+/// Returns empty table for the service
+fn eit(service: &DvbService, table_id: u8) -> Eit {
+    Eit {
+        table_id,
+        version: 0,
+        pnr: service.pnr,
+        tsid: service.tsid,
+        onid: service.onid,
+        items: Vec::new(),
+    }
+}
+
+
+/// Sub-table state: version and payload of each section
+#[derive(Default, Debug)]
+struct SubTable {
+    version: u8,
+    sections: Vec<Vec<u8>>,
+}
+
+
+/// Stateful EIT generator.
+/// Keeps version number of each sub-table and increments it (mod 32)
+/// only if content of any section has been changed since previous call
+#[derive(Debug)]
+pub struct EitGenerator {
+    /// Maximum number of events in the schedule section.
+    /// Events above 8 sections per segment appended to the last section,
+    /// events not fit into the section size skipped and appended to errors
+    pub section_events: usize,
+    /// Events not representable in EIT and skipped on generation:
+    /// channel id, event id and error. Long events could be split
+    /// before generation with `Epg::split_events`
+    pub errors: Vec<(String, u16, EitError)>,
+    /// Actual transport stream: original_network_id and transport_stream_id.
+    /// Services of other transport streams generated with other table_id.
+    /// If None all services are in the actual transport stream
+    pub actual: Option<(u16, u16)>,
+    /// Sub-tables state by service and table_id
+    state: HashMap<(DvbService, u8), SubTable>,
+}
+
+
+impl Default for EitGenerator {
+    fn default() -> Self {
+        EitGenerator {
+            section_events: 2,
+            errors: Vec::new(),
+            actual: None,
+            state: HashMap::new(),
+        }
+    }
+}
+
+
+impl EitGenerator {
+    /// Returns version number of the sub-table.
+    /// None if sub-table has not been generated yet
+    pub fn version(&self, service: &DvbService, table_id: u8) -> Option<u8> {
+        self.state.get(&(*service, table_id)).map(|v| v.version)
+    }
+
+    /// Forgets state of all service sub-tables.
+    /// Next generation starts with version 0
+    pub fn remove(&mut self, service: &DvbService) {
+        self.state.retain(|(s, _), _| s != service);
+    }
+
+    /// Returns true if service is in the actual transport stream
+    fn is_actual(&self, service: &DvbService) -> bool {
+        match self.actual {
+            Some(v) => v == (service.onid, service.tsid),
+            None => true,
+        }
+    }
+
+    /// Converts event to EIT item with running status at the time.
+    /// Invalid event appended to errors
+    fn item(&mut self, id: &str, event: &EpgEvent, time: i64) -> Option<EitItem> {
+        match event.eit_item(time) {
+            Ok(v) => Some(v),
+            Err(e) => {
+                self.errors.push((id.to_owned(), event.event_id, e));
+                None
+            }
+        }
+    }
+
+    /// Removes events not fit into the section size.
+    /// Skipped events appended to errors
+    fn overflow(&mut self, id: &str, section: &mut EitSection) {
+        for item in section.split_overflow() {
+            self.errors.push((id.to_owned(), item.event_id, EitError::SectionOverflow));
+        }
+    }
+
+    /// Updates sub-table version if content of any section has been changed
+    /// and sets version to the sections
+    fn update(&mut self, service: &DvbService, table_id: u8, sections: &mut [EitSection]) {
+        /* sections fitted with overflow() */
+        let payload: Vec<Vec<u8>> = sections.iter()
+            .map(|v| v.assemble().unwrap_or_default())
+            .collect();
+
+        let version = match self.state.get_mut(&(*service, table_id)) {
+            Some(state) => {
+                if state.sections != payload {
+                    state.version = (state.version + 1) % 32;
+                    state.sections = payload;
+                }
+                state.version
+            }
+            None => {
+                self.state.insert((*service, table_id), SubTable {
+                    version: 0,
+                    sections: payload,
+                });
+                0
+            }
+        };
+
+        for section in sections.iter_mut() {
+            section.eit.version = version;
+        }
+    }
+
+    /// Generates present/following table for the channel at the time.
+    /// Table 0x4E for the actual transport stream and 0x4F for other.
+    /// Section 0 is the event on air, section 1 is the next event.
+    /// Section 0 is empty if no event on air.
+    /// Returns None if channel not found or not mapped in `Epg::services`
+    pub fn present_following(&mut self, epg: &Epg, id: &str, time: i64) -> Option<Vec<EitSection>> {
+        let service = epg.services.service(id)?;
+        let channel = epg.channel(id)?;
+        let (present, following) = channel.now_next(time);
+        let table_id = if self.is_actual(service) { EIT_PF_ACTUAL } else { EIT_PF_OTHER };
+
+        let mut sections = Vec::with_capacity(2);
+        for (section_number, event) in [present, following].iter().enumerate() {
+            let mut eit = eit(service, table_id);
+            if let Some(v) = event.and_then(|v| self.item(id, v, time)) {
+                eit.items.push(v);
+            }
+
+            let mut section = EitSection {
+                eit,
+                section_number: section_number as u8,
+                last_section_number: 1,
+                segment_last_section_number: 1,
+                last_table_id: table_id,
+            };
+            self.overflow(id, &mut section);
+            sections.push(section);
+        }
+
+        self.update(service, table_id, &mut sections);
+        Some(sections)
+    }
+
+    /// Generates schedule tables for 8 days from the midnight (UTC) of the time.
+    /// Tables 0x50-0x51 for the actual transport stream and 0x60-0x61 for other.
+    /// Events grouped into 3-hour segments by the start time, each table
+    /// covers 4 days. Event started before the midnight and still on air
+    /// is in the first segment. Each segment has at least one section,
+    /// sections ordered by table_id and section_number.
+    /// Returns empty list if channel not found or not mapped in `Epg::services`
+    pub fn schedule(&mut self, epg: &Epg, id: &str, time: i64) -> Vec<EitSection> {
+        let (service, channel) = match (epg.services.service(id), epg.channel(id)) {
+            (Some(service), Some(channel)) => (service, channel),
+            _ => return Vec::new(),
+        };
+
+        let midnight = time - time.rem_euclid(86400);
+        let end = midnight + SCHEDULE_DAYS * 86400;
+        let segments = (SCHEDULE_DAYS * 86400 / SEGMENT) as usize;
+        let tables = segments / TABLE_SEGMENTS;
+        let first_table_id = if self.is_actual(service) { EIT_SCHEDULE_ACTUAL } else { EIT_SCHEDULE_OTHER };
+        let last_table_id = first_table_id + tables as u8 - 1;
+
+        /* items of each section by segment */
+        let mut segment_items: Vec<Vec<Vec<EitItem>>> = (0 .. segments).map(|_| Vec::new()).collect();
+        let max_events = self.section_events.max(1);
+
+        /* ended events not included */
+        for event in channel.events_range(&(time .. end)) {
+            let segment = ((event.start.max(midnight) - midnight) / SEGMENT) as usize;
+            let item = match self.item(id, event, time) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            let items = &mut segment_items[segment];
+            let next = match items.last() {
+                Some(v) => v.len() >= max_events && items.len() < SEGMENT_SECTIONS,
+                None => true,
+            };
+            if next {
+                items.push(vec![item]);
+            } else {
+                items.last_mut().unwrap().push(item);
+            }
+        }
+
+        let mut result = Vec::new();
+        let mut segment_items = segment_items.into_iter();
+
+        for table in 0 .. tables {
+            let table_id = first_table_id + table as u8;
+            let mut sections = Vec::new();
+
+            for segment in 0 .. TABLE_SEGMENTS {
+                let mut items = segment_items.next().unwrap_or_default();
+                if items.is_empty() {
+                    items.push(Vec::new());
+                }
+
+                let first = (segment * SEGMENT_SECTIONS) as u8;
+                let segment_last = first + items.len() as u8 - 1;
+                for (i, items) in items.into_iter().enumerate() {
+                    let mut eit = eit(service, table_id);
+                    eit.items = items;
+                    let mut section = EitSection {
+                        eit,
+                        section_number: first + i as u8,
+                        last_section_number: 0,
+                        segment_last_section_number: segment_last,
+                        last_table_id,
+                    };
+                    self.overflow(id, &mut section);
+                    sections.push(section);
+                }
+            }
+
+            let last = sections.last().map(|v| v.section_number).unwrap_or(0);
+            for section in &mut sections {
+                section.last_section_number = last;
+            }
+
+            self.update(service, table_id, &mut sections);
+            result.append(&mut sections);
+        }
+
+        result
+    }
+}
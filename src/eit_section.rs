@@ -0,0 +1,219 @@
+use std::convert::TryFrom;
+
+use mpegts::psi::{
+    Eit,
+    EitItem,
+    Psi,
+};
+
+use crate::{
+    dvb_time,
+    EitError,
+};
+
+
+const PACKET_SIZE: usize = 188;
+
+/// Section header size: from table_id to last_table_id
+const HEADER_SIZE: usize = 14;
+
+/// Event header size: from event_id to descriptors_loop_length
+const EVENT_SIZE: usize = 12;
+
+
+/// MPEG-2 CRC32 (ISO/IEC 13818-1 Annex A)
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &b in data {
+        crc ^= u32::from(b) << 24;
+        for _ in 0 .. 8 {
+            crc = if crc & 0x8000_0000 != 0 { (crc << 1) ^ 0x04C1_1DB7 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+
+/// Collects sections from TS packets. Section starts in the packet
+/// with payload_unit_start_indicator after the pointer field
+fn sections(ts: &[u8]) -> Vec<Vec<u8>> {
+    let mut result: Vec<Vec<u8>> = Vec::new();
+
+    for packet in ts.chunks_exact(PACKET_SIZE) {
+        if packet[1] & 0x40 != 0 {
+            let pointer = usize::from(packet[4]);
+            result.push(packet[5 + pointer ..].to_vec());
+        } else if let Some(section) = result.last_mut() {
+            section.extend_from_slice(&packet[4 ..]);
+        }
+    }
+
+    for section in &mut result {
+        if section.len() >= 3 {
+            let size = 3 + ((usize::from(section[1] & 0x0F) << 8) | usize::from(section[2]));
+            section.truncate(size);
+        }
+    }
+
+    result
+}
+
+
+/// Returns offset of each event in the section with CRC
+fn events(section: &[u8]) -> Vec<usize> {
+    let mut result = Vec::new();
+    let end = section.len().saturating_sub(4);
+    let mut skip = HEADER_SIZE;
+
+    while skip + EVENT_SIZE <= end {
+        result.push(skip);
+        let size = (usize::from(section[skip + 10] & 0x0F) << 8) | usize::from(section[skip + 11]);
+        skip += EVENT_SIZE + size;
+    }
+
+    result
+}
+
+
+/// Appends TS packets with the section. Stuffing with 0xFF
+fn packetize(section: &[u8], pid: u16, cc: &mut u8, dst: &mut Vec<u8>) {
+    let mut payload = Vec::with_capacity(section.len() + 1);
+    payload.push(0); /* pointer field */
+    payload.extend_from_slice(section);
+
+    for (i, chunk) in payload.chunks(PACKET_SIZE - 4).enumerate() {
+        let pusi = if i == 0 { 0x40 } else { 0x00 };
+        dst.extend_from_slice(&[
+            0x47,
+            pusi | ((pid >> 8) as u8 & 0x1F),
+            pid as u8,
+            0x10 | (*cc & 0x0F),
+        ]);
+        dst.extend_from_slice(chunk);
+        dst.resize(dst.len() + PACKET_SIZE - 4 - chunk.len(), 0xFF);
+        *cc = (*cc + 1) & 0x0F;
+    }
+}
+
+
+/// EIT section: table header and section events with its number in the sub-table.
+/// `Eit::demux` numbers sections by itself, use `EitSection::demux` to keep numbering
+pub struct EitSection {
+    /// Table header and events of the section
+    pub eit: Eit,
+    pub section_number: u8,
+    pub last_section_number: u8,
+    /// Last section number of the segment.
+    /// Equal to `last_section_number` for present/following
+    pub segment_last_section_number: u8,
+    /// Last table_id of the schedule. Equal to `table_id` for present/following
+    pub last_table_id: u8,
+}
+
+
+impl EitSection {
+    /// Parses complete section from PSI. Event start time and duration
+    /// decoded with `dvb_time`: undefined or invalid start is `u64::MAX`,
+    /// duration is `u32::MAX`. Returns None if section is too short
+    pub fn parse(psi: &Psi) -> Option<EitSection> {
+        let section = &psi.buffer;
+        if section.len() < HEADER_SIZE + 4 {
+            return None;
+        }
+
+        let mut eit = Eit::default();
+        eit.parse(psi);
+
+        for (item, skip) in eit.items.iter_mut().zip(events(section)) {
+            let mut start = [0; 5];
+            start.copy_from_slice(&section[skip + 2 .. skip + 7]);
+            let mut duration = [0; 3];
+            duration.copy_from_slice(&section[skip + 7 .. skip + 10]);
+
+            item.start = dvb_time::decode_time(&start).ok()
+                .and_then(|v| u64::try_from(v).ok())
+                .unwrap_or(u64::MAX);
+            item.duration = dvb_time::decode_duration(&duration)
+                .map(|v| v as u32)
+                .unwrap_or(u32::MAX);
+        }
+
+        Some(EitSection {
+            eit,
+            section_number: section[6],
+            last_section_number: section[7],
+            segment_last_section_number: section[12],
+            last_table_id: section[13],
+        })
+    }
+
+    /// Returns first section produced by `Eit::demux`.
+    /// Events above the section size limit (4096 bytes) are in the next sections
+    fn first(&self) -> Option<Vec<u8>> {
+        let mut cc = 0;
+        let mut ts = Vec::new();
+        self.eit.demux(0, &mut cc, &mut ts);
+
+        match sections(&ts).into_iter().next() {
+            Some(v) if v.len() >= HEADER_SIZE + 4 => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Removes events not fit into one section and returns them
+    pub fn split_overflow(&mut self) -> Vec<EitItem> {
+        let count = self.first().map(|v| events(&v).len()).unwrap_or(0);
+        if count < self.eit.items.len() {
+            self.eit.items.split_off(count)
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Returns section data with CRC.
+    /// Event start time and duration encoded with `dvb_time`,
+    /// values out of range encoded as undefined (all bits set).
+    /// Returns `SectionOverflow` if events not fit into one section
+    pub fn assemble(&self) -> Result<Vec<u8>, EitError> {
+        let mut section = match self.first() {
+            Some(v) => v,
+            None => return Ok(Vec::new()),
+        };
+
+        let events = events(&section);
+        if events.len() < self.eit.items.len() {
+            return Err(EitError::SectionOverflow);
+        }
+
+        section[6] = self.section_number;
+        section[7] = self.last_section_number;
+        section[12] = self.segment_last_section_number;
+        section[13] = self.last_table_id;
+
+        for (item, skip) in self.eit.items.iter().zip(events) {
+            let start = i64::try_from(item.start).ok()
+                .and_then(|v| dvb_time::encode_time(v).ok())
+                .unwrap_or([0xFF; 5]);
+            let duration = dvb_time::encode_duration(i64::from(item.duration))
+                .unwrap_or([0xFF; 3]);
+
+            section[skip + 2 .. skip + 7].copy_from_slice(&start);
+            section[skip + 7 .. skip + 10].copy_from_slice(&duration);
+        }
+
+        let size = section.len() - 4;
+        let crc = crc32(&section[.. size]);
+        section[size ..].copy_from_slice(&crc.to_be_bytes());
+
+        Ok(section)
+    }
+
+    /// Appends TS packets with the section
+    pub fn demux(&self, pid: u16, cc: &mut u8, dst: &mut Vec<u8>) -> Result<(), EitError> {
+        let section = self.assemble()?;
+        if ! section.is_empty() {
+            packetize(&section, pid, cc, dst);
+        }
+        Ok(())
+    }
+}
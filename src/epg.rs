@@ -1,24 +1,49 @@
 use std::{
     str,
-    fs::File,
+    fs::{
+        self,
+        File,
+    },
+    ops::Range,
     io::{
         self,
         BufRead,
         BufReader,
+        BufWriter,
+        Read,
         Write,
     },
     collections::HashMap,
 };
 
-use libflate::gzip;
+use libflate::{
+    gzip,
+    lz77,
+};
 
-use http::{
-    HttpClient,
-    HttpClientError,
+#[cfg(feature = "serde")]
+use serde::{
+    Deserialize,
+    Serialize,
 };
 
+use http::HttpClientError;
+
+use mpegts::psi::Eit;
+
 use crate::{
+    EitError,
+    JsonReaderError,
     EpgChannel,
+    EpgEvent,
+    EpgOptions,
+    EpgSources,
+    DvbService,
+    ServiceMap,
+    Timeshift,
+    Compression,
+    LoadPolicy,
+    split_url,
     read_xml::{
         read_xml_tv,
         XmlReaderError,
@@ -27,6 +52,24 @@ use crate::{
         write_xml_tv,
         XmlWriterError,
     },
+    snapshot::{
+        read_snapshot,
+        write_snapshot,
+        SnapshotError,
+    },
+    service_map::ServiceMapError,
+};
+
+#[cfg(feature = "tz")]
+use crate::LocalTimeDiagnostic;
+
+#[cfg(feature = "json")]
+use crate::{
+    read_json::{
+        is_json,
+        read_json,
+    },
+    write_json::write_json,
 };
 
 
@@ -43,69 +86,548 @@ pub enum EpgError {
     XmlReader(XmlReaderError),
     #[error_from("Epg: {}", 0)]
     XmlWriter(XmlWriterError),
+    #[error_from("Epg: {}", 0)]
+    Json(JsonReaderError),
+    #[error_from("Epg: {}", 0)]
+    Snapshot(SnapshotError),
+    #[error_from("Epg: {}", 0)]
+    ServiceMap(ServiceMapError),
     #[error_kind("Epg: unknown source type")]
     UnknownSourceType,
+    #[error_kind("Epg: unexpected http status {}", 0)]
+    HttpStatus(usize),
+    #[error_kind("Epg: too many redirects")]
+    TooManyRedirects,
+    #[error_kind("Epg: response size limit exceeded")]
+    TooLarge,
+    #[error_kind("Epg: source thread panicked")]
+    SourceThread,
+    #[error_kind("Epg: {} compression not supported", 0)]
+    UnsupportedCompression(&'static str),
+    #[error_kind("Epg: xml not found in archive")]
+    ArchiveEntryNotFound,
 }
 
 
 type Result<T> = std::result::Result<T, EpgError>;
 
 
-fn is_gzip<R: io::BufRead>(src: &mut R) -> io::Result<bool> {
-    static GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
-    let buf = src.fill_buf()?;
-    Ok(buf.len() >= 2 && buf[.. 2] == GZIP_MAGIC)
+/// Maximum gzip level: LZ77 window of 32 KiB
+const GZIP_LEVEL_MAX: u32 = 6;
+
+
+/// Guide format
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EpgFormat {
+    XmlTv,
+    #[cfg(feature = "json")]
+    Json,
 }
 
 
+impl EpgFormat {
+    fn content_type(self) -> &'static str {
+        match self {
+            EpgFormat::XmlTv => "application/xml",
+            #[cfg(feature = "json")]
+            EpgFormat::Json => "application/json",
+        }
+    }
+}
+
+
+/// HTTP method for upload in `Epg::save`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UploadMethod {
+    Put,
+    Post,
+}
+
+
+impl UploadMethod {
+    fn name(self) -> &'static str {
+        match self {
+            UploadMethod::Put => "PUT",
+            UploadMethod::Post => "POST",
+        }
+    }
+}
+
+
+/// Output settings for `Epg::write_with` and `Epg::save_with`
+#[derive(Debug, Clone, PartialEq)]
+pub struct WriteOptions {
+    pub format: EpgFormat,
+    pub compression: Compression,
+    /// Compression level. Default level for the codec if None.
+    /// Clamped to the codec range: 0-9, 0-22 for zstd.
+    /// gzip level approximated with LZ77 window size: 0 is no compression,
+    /// 1-6 is window from 1 KiB to 32 KiB, levels above 6 are clamped to 6
+    pub level: Option<u32>,
+    /// HTTP method for `http://` and `https://` destinations
+    pub method: UploadMethod,
+    /// Timeouts, retries and redirects for `http://` and `https://` destinations
+    pub policy: LoadPolicy,
+}
+
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions {
+            format: EpgFormat::XmlTv,
+            compression: Compression::None,
+            level: None,
+            method: UploadMethod::Put,
+            policy: LoadPolicy::default(),
+        }
+    }
+}
+
+
+impl WriteOptions {
+    /// Selects format and compression by the file name extension:
+    /// `.xml`, `.json`, with optional `.gz`, `.xz`, `.bz2`, `.zst`.
+    /// URL query string is ignored
+    pub fn from_path(path: &str) -> Self {
+        let path = match path.find('?') {
+            Some(i) => &path[.. i],
+            None => path,
+        };
+        let compression = Compression::from_path(path);
+
+        #[cfg(feature = "json")]
+        {
+            let path = compression.strip_extension(path).to_lowercase();
+            if path.ends_with(".json") {
+                return WriteOptions {
+                    format: EpgFormat::Json,
+                    compression,
+                    ..Default::default()
+                };
+            }
+        }
+
+        WriteOptions {
+            format: EpgFormat::XmlTv,
+            compression,
+            ..Default::default()
+        }
+    }
+}
+
+
+/// Reads uncompressed guide. JSON detected by the first character
+fn read_guide<R: BufRead>(epg: &mut Epg, src: &mut R) -> Result<()> {
+    #[cfg(feature = "json")]
+    {
+        if is_json(src)? {
+            read_json(epg, src)?;
+            return Ok(());
+        }
+    }
+
+    read_xml_tv(epg, src)?;
+    Ok(())
+}
+
+
+#[cfg(feature = "zip")]
+fn read_zip<R: BufRead>(epg: &mut Epg, src: &mut R) -> Result<()> {
+    use std::io::Cursor;
+
+    fn zip_error(e: zip::result::ZipError) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+
+    /* zip archive requires seekable source */
+    let mut buf = Vec::new();
+    src.read_to_end(&mut buf)?;
+    let mut archive = zip::ZipArchive::new(Cursor::new(buf)).map_err(zip_error)?;
+
+    let mut index = None;
+    for i in 0 .. archive.len() {
+        let file = archive.by_index(i).map_err(zip_error)?;
+        let name = file.name();
+        let found = match &epg.options.archive_entry {
+            Some(v) => name == v,
+            None => name.to_lowercase().ends_with(".xml"),
+        };
+        if found {
+            index = Some(i);
+            break;
+        }
+    }
+
+    let index = index.ok_or(EpgError::ArchiveEntryNotFound)?;
+    let file = archive.by_index(index).map_err(zip_error)?;
+    let mut buf = BufReader::new(file);
+    read_guide(epg, &mut buf)?;
+
+    Ok(())
+}
+
+
+/// Electronic program guide. With `serde` feature serialized as:
+///
+/// ```text
+/// {
+///     "channels": { "<channel id>": EpgChannel, ... }
+/// }
+/// ```
+///
+/// Load options, source backends, service map and timeshift channels
+/// are not serialized
 #[derive(Default, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
 pub struct Epg {
     pub channels: HashMap<String, EpgChannel>,
+    /// Options applied on load
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub options: EpgOptions,
+    /// Source backends for `load`
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub sources: EpgSources,
+    /// Channel id to DVB service mapping for EIT output and import
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub services: ServiceMap,
+    /// Virtual timeshift channels by id. Rebuilt from parent on each load
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub timeshifts: HashMap<String, Timeshift>,
+    /// Channels built from `timeshifts`. Kept apart from `channels`,
+    /// so not saved, not written to the snapshot and not pruned
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub timeshift_channels: HashMap<String, EpgChannel>,
+    /// EIT items skipped on import with `parse_eit`:
+    /// channel id, event id and error
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub eit_errors: Vec<(String, u16, EitError)>,
+    /// Local times at daylight saving time transition found on last load.
+    /// Cleared before each read
+    #[cfg(feature = "tz")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub diagnostics: Vec<LocalTimeDiagnostic>,
 }
 
 
 impl Epg {
+    /// Loads XMLTV from the source with backend selected by the URL scheme
     pub fn load<R: AsRef<str>>(&mut self, src: R) -> Result<()> {
-        let src = src.as_ref();
-
-        let mut i = src.splitn(2, "://");
-        let scheme = i.next().unwrap();
-        let (scheme, path) = match i.next() {
-            Some(v) => (scheme, v),
-            None => ("file", scheme),
-        };
+        let mut reader = self.sources.open(src.as_ref())?;
+        self.read(&mut reader)
+    }
 
-        match scheme {
-            "file" => {
-                let file = File::open(path)?;
-                let mut buf = BufReader::new(file);
-                self.read(&mut buf)
+    /// Loads guide only if source is modified since previous load.
+    /// Returns false if source is not modified and guide left unchanged
+    pub fn load_modified<R: AsRef<str>>(&mut self, src: R) -> Result<bool> {
+        match self.sources.open_modified(src.as_ref())? {
+            Some(mut reader) => {
+                self.read(&mut reader)?;
+                Ok(true)
             }
-            "http" | "https" => {
-                let mut client = HttpClient::new(src)?;
-                client.get()?;
-                self.read(&mut client)
-            }
-            _ => Err(EpgError::UnknownSourceType),
+            None => Ok(false),
         }
     }
 
-    #[inline]
+    /// Reads XMLTV or JSON guide from the source.
+    /// Compressed source detected by magic bytes
     pub fn read<R: BufRead>(&mut self, src: &mut R) -> Result<()> {
-        if is_gzip(src)? {
-            let decoder = gzip::Decoder::new(src)?;
-            let mut buf = BufReader::new(decoder);
-            read_xml_tv(self, &mut buf)?;
-        } else {
-            read_xml_tv(self, src)?;
+        #[cfg(feature = "tz")]
+        self.diagnostics.clear();
+
+        match Compression::detect(src)? {
+            Compression::None => {
+                read_guide(self, src)?;
+            }
+            Compression::Gzip => {
+                let decoder = gzip::Decoder::new(src)?;
+                let mut buf = BufReader::new(decoder);
+                read_guide(self, &mut buf)?;
+            }
+            #[cfg(feature = "xz2")]
+            Compression::Xz => {
+                let decoder = xz2::bufread::XzDecoder::new(src);
+                let mut buf = BufReader::new(decoder);
+                read_guide(self, &mut buf)?;
+            }
+            #[cfg(feature = "bzip2")]
+            Compression::Bzip2 => {
+                let decoder = bzip2::bufread::BzDecoder::new(src);
+                let mut buf = BufReader::new(decoder);
+                read_guide(self, &mut buf)?;
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => {
+                let decoder = zstd::stream::read::Decoder::with_buffer(src)?;
+                let mut buf = BufReader::new(decoder);
+                read_guide(self, &mut buf)?;
+            }
+            #[cfg(feature = "zip")]
+            Compression::Zip => {
+                read_zip(self, src)?;
+            }
+            #[allow(unreachable_patterns)]
+            v => return Err(EpgError::UnsupportedCompression(v.name())),
         }
 
+        self.update_timeshifts();
         Ok(())
     }
 
+    /// Appends virtual channel with id and names of the parent channel
+    /// followed by the suffix, and events shifted by the offset in seconds.
+    /// Returns id of the virtual channel
+    pub fn add_timeshift(&mut self, parent: &str, offset: i64, suffix: &str) -> String {
+        let id = format!("{}{}", parent, suffix);
+        let timeshift = Timeshift {
+            name_suffix: format!(" {}", suffix.trim_start()),
+            ..Timeshift::new(parent, offset)
+        };
+
+        if let Some(channel) = self.channels.get(parent) {
+            self.timeshift_channels.insert(id.clone(), timeshift.apply(channel));
+        }
+        self.timeshifts.insert(id.clone(), timeshift);
+
+        id
+    }
+
+    /// Rebuilds virtual channels from the parent channels.
+    /// Virtual channel removed if parent channel not found
+    pub fn update_timeshifts(&mut self) {
+        let channels = &self.channels;
+        self.timeshift_channels = self.timeshifts.iter()
+            .filter_map(|(id, timeshift)| {
+                let parent = channels.get(&timeshift.parent)?;
+                Some((id.clone(), timeshift.apply(parent)))
+            })
+            .collect();
+    }
+
+    /// Returns channel or virtual timeshift channel by id
+    pub fn channel(&self, id: &str) -> Option<&EpgChannel> {
+        self.channels.get(id).or_else(|| self.timeshift_channels.get(id))
+    }
+
+    /// Imports events from EIT. Channel selected by the service mapping.
+    /// Returns channel id or None if service not mapped
+    pub fn parse_eit(&mut self, eit: &Eit) -> Option<&str> {
+        let service = DvbService {
+            onid: eit.onid,
+            tsid: eit.tsid,
+            pnr: eit.pnr,
+        };
+
+        let id = self.services.channel(&service)?;
+        let channel = self.channels
+            .entry(id.to_owned())
+            .or_default();
+        for (event_id, e) in channel.parse(eit) {
+            self.eit_errors.push((id.to_owned(), event_id, e));
+        }
+
+        self.update_timeshifts();
+        self.services.channel(&service)
+    }
+
+    /// Returns event on air at the time
+    pub fn event_at(&self, channel: &str, time: i64) -> Option<&EpgEvent> {
+        self.channel(channel)?.event_at(time)
+    }
+
+    /// Returns current and next events for the channel at the time
+    pub fn now_next(&self, channel: &str, time: i64) -> (Option<&EpgEvent>, Option<&EpgEvent>) {
+        match self.channel(channel) {
+            Some(v) => v.now_next(time),
+            None => (None, None),
+        }
+    }
+
+    /// Returns channel events overlapping the time range
+    pub fn events_range(&self, channel: &str, range: &Range<i64>) -> &[EpgEvent] {
+        match self.channel(channel) {
+            Some(v) => v.events_range(range),
+            None => &[],
+        }
+    }
+
+    /// Returns current and next events for all channels
+    /// including virtual timeshift channels at the time
+    pub fn now_next_all(&self, time: i64) -> HashMap<&str, (Option<&EpgEvent>, Option<&EpgEvent>)> {
+        self.channels.iter()
+            .chain(self.timeshift_channels.iter())
+            .map(|(id, channel)| (id.as_str(), channel.now_next(time)))
+            .collect()
+    }
+
+    /// Removes events outside of the time range in all channels.
+    /// Virtual timeshift channels rebuilt from the pruned parent
+    pub fn prune(&mut self, range: &Range<i64>) {
+        for channel in self.channels.values_mut() {
+            channel.prune(range);
+        }
+        self.update_timeshifts();
+    }
+
+    /// Splits events longer than `max_duration` in all channels
+    pub fn split_events(&mut self, max_duration: i64) {
+        for channel in self.channels.values_mut() {
+            channel.split_events(max_duration);
+        }
+        self.update_timeshifts();
+    }
+
     #[inline]
     pub fn write<W: Write>(&self, dst: W) -> Result<()> {
-        write_xml_tv(self, dst)?;
+        self.write_format(dst, EpgFormat::XmlTv)
+    }
+
+    /// Writes guide in the given format
+    pub fn write_format<W: Write>(&self, dst: W, format: EpgFormat) -> Result<()> {
+        match format {
+            EpgFormat::XmlTv => write_xml_tv(self, dst)?,
+            #[cfg(feature = "json")]
+            EpgFormat::Json => write_json(self, dst)?,
+        };
         Ok(())
     }
+
+    /// Writes binary snapshot of all channels for fast restore
+    #[inline]
+    pub fn write_snapshot<W: Write>(&self, dst: W) -> Result<()> {
+        write_snapshot(self, dst)?;
+        Ok(())
+    }
+
+    /// Restores channels from the binary snapshot.
+    /// Replaces all channels in the guide
+    #[inline]
+    pub fn read_snapshot<R: Read>(&mut self, src: &mut R) -> Result<()> {
+        read_snapshot(self, src)?;
+        self.update_timeshifts();
+        Ok(())
+    }
+
+    /// Writes guide with compression
+    pub fn write_with<W: Write>(&self, dst: W, options: &WriteOptions) -> Result<W> {
+        let format = options.format;
+
+        match options.compression {
+            Compression::None => {
+                let mut dst = dst;
+                self.write_format(&mut dst, format)?;
+                Ok(dst)
+            }
+            Compression::Gzip => {
+                /* level 0 is no compression, higher level selects larger LZ77 window */
+                let encode = match options.level.map(|v| v.min(GZIP_LEVEL_MAX)) {
+                    Some(0) => gzip::EncodeOptions::new().no_compression(),
+                    Some(v) => gzip::EncodeOptions::with_lz77(
+                        lz77::DefaultLz77Encoder::with_window_size(1 << (9 + v))),
+                    None => gzip::EncodeOptions::new(),
+                };
+                let mut encoder = gzip::Encoder::with_options(dst, encode)?;
+                self.write_format(&mut encoder, format)?;
+                Ok(encoder.finish().into_result()?)
+            }
+            #[cfg(feature = "xz2")]
+            Compression::Xz => {
+                let level = options.level.unwrap_or(6).min(9);
+                let mut encoder = xz2::write::XzEncoder::new(dst, level);
+                self.write_format(&mut encoder, format)?;
+                Ok(encoder.finish()?)
+            }
+            #[cfg(feature = "bzip2")]
+            Compression::Bzip2 => {
+                let level = match options.level {
+                    Some(v) if v <= 3 => bzip2::Compression::Fastest,
+                    Some(v) if v >= 7 => bzip2::Compression::Best,
+                    _ => bzip2::Compression::Default,
+                };
+                let mut encoder = bzip2::write::BzEncoder::new(dst, level);
+                self.write_format(&mut encoder, format)?;
+                Ok(encoder.finish()?)
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => {
+                let level = options.level.unwrap_or(3).min(22) as i32;
+                let mut encoder = zstd::stream::write::Encoder::new(dst, level)?;
+                self.write_format(&mut encoder, format)?;
+                Ok(encoder.finish()?)
+            }
+            #[allow(unreachable_patterns)]
+            v => Err(EpgError::UnsupportedCompression(v.name())),
+        }
+    }
+
+    /// Saves guide to the destination with backend selected by the URL scheme:
+    /// `file://` or path without scheme, `http://` or `https://` upload.
+    /// Format and compression selected by the file name extension
+    #[inline]
+    pub fn save<R: AsRef<str>>(&self, dst: R) -> Result<()> {
+        let dst = dst.as_ref();
+        let (_, path) = split_url(dst);
+        self.save_with(dst, &WriteOptions::from_path(path))
+    }
+
+    fn save_tmp(&self, tmp: &str, options: &WriteOptions) -> Result<()> {
+        let file = File::create(tmp)?;
+        let mut buf = self.write_with(BufWriter::new(file), options)?;
+        buf.flush()?;
+        buf.get_ref().sync_all()?;
+        Ok(())
+    }
+
+    /// Saves guide to the file. File replaced atomically:
+    /// guide written to the temporary file and then renamed
+    fn save_file(&self, path: &str, options: &WriteOptions) -> Result<()> {
+        let tmp = format!("{}.tmp", path);
+
+        let result = self.save_tmp(&tmp, options)
+            .and_then(|_| fs::rename(&tmp, path).map_err(EpgError::from));
+
+        if result.is_err() {
+            fs::remove_file(&tmp).ok();
+        }
+
+        result
+    }
+
+    /// Uploads guide with HTTP PUT or POST request.
+    /// Uncompressed guide sent with the format content type,
+    /// gzip with the format content type and `content-encoding: gzip`,
+    /// other compression as `application/octet-stream`
+    fn save_http(&self, url: &str, options: &WriteOptions) -> Result<()> {
+        let body = self.write_with(Vec::new(), options)?;
+        let mut headers = Vec::with_capacity(2);
+        match options.compression {
+            Compression::None => {
+                headers.push(("content-type", options.format.content_type()));
+            }
+            Compression::Gzip => {
+                headers.push(("content-type", options.format.content_type()));
+                headers.push(("content-encoding", "gzip"));
+            }
+            _ => {
+                headers.push(("content-type", "application/octet-stream"));
+            }
+        };
+
+        let client = options.policy.upload(url, options.method.name(), &headers, &body)?;
+        match client.response.code {
+            200 ..= 299 => Ok(()),
+            v => Err(EpgError::HttpStatus(v)),
+        }
+    }
+
+    /// Saves guide to the destination with given format and compression.
+    /// Local file replaced atomically: guide written to the temporary file
+    /// and then renamed
+    pub fn save_with<R: AsRef<str>>(&self, dst: R, options: &WriteOptions) -> Result<()> {
+        let dst = dst.as_ref();
+        match split_url(dst) {
+            ("file", path) => self.save_file(path, options),
+            ("http", _) | ("https", _) => self.save_http(dst, options),
+            _ => Err(EpgError::UnknownSourceType),
+        }
+    }
 }
@@ -0,0 +1,198 @@
+use std::{
+    thread,
+    collections::{
+        BTreeMap,
+        HashMap,
+    },
+};
+
+use crate::{
+    Epg,
+    EpgChannel,
+    EpgError,
+    EpgEvent,
+    EpgOptions,
+    EpgSources,
+    Timeshift,
+};
+
+
+/// Source for the aggregator
+#[derive(Debug, Clone)]
+pub struct AggregatorSource {
+    /// Source address for `Epg::load`
+    pub url: String,
+    /// Source with higher priority wins in overlapped time slots
+    pub priority: i32,
+    /// Load options: channel filters and mapping, time window
+    pub options: EpgOptions,
+}
+
+
+/// Aggregation result
+#[derive(Default, Debug)]
+pub struct Aggregated {
+    pub epg: Epg,
+    /// Index of the source supplied each event.
+    /// Channel id to the list in the same order as channel events
+    pub origin: HashMap<String, Vec<usize>>,
+    /// Load errors with source index
+    pub errors: Vec<(usize, EpgError)>,
+}
+
+
+/// Combines guides from multiple sources.
+/// Sources are loaded in parallel and merged per channel: events of the
+/// source with lower priority fill time slots free in the higher sources
+#[derive(Default, Debug, Clone)]
+pub struct EpgAggregator {
+    pub sources: Vec<AggregatorSource>,
+    /// Source backends for all sources
+    pub backends: EpgSources,
+    /// Virtual timeshift channels built from merged channels
+    pub timeshifts: HashMap<String, Timeshift>,
+}
+
+
+/// Event stop time. Event without stop time lasts till the next event
+/// of the same source, last event without stop time takes no time
+#[inline]
+fn event_stop(event: &EpgEvent, next: Option<&EpgEvent>) -> i64 {
+    match event.stop {
+        Some(v) if v > event.start => v,
+        _ => next.map(|v| v.start).unwrap_or(event.start).max(event.start),
+    }
+}
+
+
+/// Accepted events of the channel ordered by start time:
+/// event, source index and event stop time
+#[derive(Default)]
+struct Timeline {
+    events: BTreeMap<i64, (EpgEvent, usize, i64)>,
+}
+
+
+impl Timeline {
+    fn is_free(&self, start: i64, stop: i64) -> bool {
+        if self.events.contains_key(&start) {
+            return false;
+        }
+
+        if let Some((_, (_, _, prev_stop))) = self.events.range(.. start).next_back() {
+            if *prev_stop > start {
+                return false;
+            }
+        }
+
+        if let Some((next_start, _)) = self.events.range(start ..).next() {
+            if *next_start < stop {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn insert(&mut self, event: EpgEvent, origin: usize, stop: i64) {
+        if self.is_free(event.start, stop) {
+            self.events.insert(event.start, (event, origin, stop));
+        }
+    }
+}
+
+
+impl EpgAggregator {
+    /// Appends source
+    pub fn add<S: Into<String>>(&mut self, url: S, priority: i32, options: EpgOptions) {
+        self.sources.push(AggregatorSource {
+            url: url.into(),
+            priority,
+            options,
+        });
+    }
+
+    /// Loads all sources in parallel and merges result
+    pub fn load(&self) -> Aggregated {
+        let handles: Vec<_> = self.sources.iter().map(|source| {
+            let url = source.url.clone();
+            let mut epg = Epg {
+                options: source.options.clone(),
+                sources: self.backends.clone(),
+                ..Default::default()
+            };
+
+            thread::spawn(move || {
+                epg.load(&url)?;
+                Ok(epg)
+            })
+        }).collect();
+
+        let mut list = Vec::new();
+        let mut result = Aggregated::default();
+
+        for (index, handle) in handles.into_iter().enumerate() {
+            match handle.join() {
+                Ok(Ok(epg)) => list.push((index, epg)),
+                Ok(Err(e)) => result.errors.push((index, e)),
+                Err(_) => result.errors.push((index, EpgError::SourceThread)),
+            };
+        }
+
+        /* stable sort: on equal priority first source wins */
+        list.sort_by(|(a, _), (b, _)| {
+            self.sources[*b].priority.cmp(&self.sources[*a].priority)
+        });
+
+        self.merge(list, &mut result);
+
+        result.epg.timeshifts = self.timeshifts.clone();
+        result.epg.update_timeshifts();
+        for (id, timeshift) in &self.timeshifts {
+            if let Some(origin) = result.origin.get(&timeshift.parent).cloned() {
+                result.origin.insert(id.clone(), origin);
+            }
+        }
+
+        result
+    }
+
+    fn merge(&self, list: Vec<(usize, Epg)>, result: &mut Aggregated) {
+        let mut timelines: HashMap<String, (EpgChannel, Timeline)> = HashMap::new();
+
+        for (index, epg) in list {
+            for (id, channel) in epg.channels {
+                let (target, timeline) = timelines
+                    .entry(id)
+                    .or_default();
+
+                for (lang, name) in channel.name {
+                    target.name.entry(lang).or_insert(name);
+                }
+
+                let mut events = channel.events.into_iter().peekable();
+                while let Some(event) = events.next() {
+                    let stop = event_stop(&event, events.peek());
+                    timeline.insert(event, index, stop);
+                }
+            }
+
+            #[cfg(feature = "tz")]
+            result.epg.diagnostics.extend(epg.diagnostics);
+        }
+
+        for (id, (mut channel, timeline)) in timelines {
+            let mut origin = Vec::with_capacity(timeline.events.len());
+            for (_, (event, index, _)) in timeline.events {
+                channel.events.push(event);
+                origin.push(index);
+            }
+
+            /* source event ids kept, colliding ids replaced */
+            channel.sort_events();
+            channel.unique_event_ids();
+            result.origin.insert(id.clone(), origin);
+            result.epg.channels.insert(id, channel);
+        }
+    }
+}
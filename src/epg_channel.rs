@@ -1,42 +1,248 @@
-use std::collections::HashMap;
+use std::{
+    cmp::Ordering,
+    convert::TryFrom,
+    ops::Range,
+    collections::{
+        HashMap,
+        HashSet,
+    },
+};
 
 use mpegts::psi::Eit;
 
-use crate::EpgEvent;
+#[cfg(feature = "serde")]
+use serde::{
+    Deserialize,
+    Serialize,
+};
 
+use crate::{
+    EitError,
+    EpgEvent,
+};
 
-#[derive(Default, Debug)]
+
+/// Channel with events sorted by start time.
+/// With `serde` feature serialized as:
+///
+/// ```text
+/// {
+///     "name": { "<lang>": "<channel name>", ... },
+///     "events": [ EpgEvent, ... ]
+/// }
+/// ```
+///
+/// `last_event_start` is not serialized and restored from events
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "crate::serde_util::ChannelData"))]
 pub struct EpgChannel {
     /// Channel names list
     pub name: HashMap<String, String>,
     /// Channel events list
     pub events: Vec<EpgEvent>,
-    /// Start time for last event
-    pub last_event_start: u64,
+    /// Start time for last event. `i64::MIN` if channel has no events
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub last_event_start: i64,
+}
+
+
+/// Returns allocator of event ids not used by the events.
+/// Allocator returns the given id if all ids are used
+fn id_allocator(events: &[EpgEvent]) -> impl FnMut(u16) -> u16 {
+    let mut used: HashSet<u16> = events.iter().map(|v| v.event_id).collect();
+    let mut event_id = events.iter().map(|v| v.event_id).max().unwrap_or(0);
+
+    move |current: u16| {
+        for _ in 0 .. u16::MAX {
+            event_id = event_id.wrapping_add(1);
+            if used.insert(event_id) {
+                return event_id;
+            }
+        }
+        current
+    }
+}
+
+
+impl Default for EpgChannel {
+    fn default() -> Self {
+        EpgChannel {
+            name: HashMap::new(),
+            events: Vec::new(),
+            last_event_start: i64::MIN,
+        }
+    }
 }
 
 
 impl EpgChannel {
-    pub fn parse(&mut self, eit: &Eit) {
+    /// Appends events from EIT. Items with undefined or out of range
+    /// start time or duration out of range are skipped.
+    /// Item with undefined duration appended without stop time.
+    /// Returns event id and error for each skipped item
+    pub fn parse(&mut self, eit: &Eit) -> Vec<(u16, EitError)> {
+        let mut errors = Vec::new();
+
         for eit_item in &eit.items {
-            self.events.push(EpgEvent::from(eit_item));
+            match EpgEvent::try_from(eit_item) {
+                Ok(v) => self.events.push(v),
+                Err(e) => errors.push((eit_item.event_id, e)),
+            }
         }
+
         self.sort();
+        errors
     }
 
-    pub fn sort(&mut self) {
-        if self.events.is_empty() {
+    /// Splits events longer than `max_duration` into consecutive parts
+    /// with the same content. Use `EIT_MAX_DURATION` to fit events into EIT.
+    /// First part keeps event id, next parts get unused ids.
+    /// Other events not renumbered
+    pub fn split_events(&mut self, max_duration: i64) {
+        if max_duration <= 0 {
             return;
         }
 
-        self.events.sort_by(|a, b| a.start.cmp(&b.start));
+        let mut next_id = id_allocator(&self.events);
+
+        let mut events = Vec::with_capacity(self.events.len());
+        for event in self.events.drain(..) {
+            let stop = match event.stop {
+                Some(v) if v - event.start > max_duration => v,
+                _ => {
+                    events.push(event);
+                    continue;
+                }
+            };
+
+            let mut start = event.start;
+            while start < stop {
+                let mut part = event.clone();
+                if start != event.start {
+                    part.event_id = next_id(event.event_id);
+                }
+                part.start = start;
+                start = stop.min(start.saturating_add(max_duration));
+                part.stop = Some(start);
+                events.push(part);
+            }
+        }
+
+        self.events = events;
+        self.sort_events();
+    }
 
-        self.last_event_start = self.events.last().unwrap().start;
+    /// Gives unused ids to events with id of the earlier event.
+    /// Other events not renumbered
+    pub (crate) fn unique_event_ids(&mut self) {
+        let mut next_id = id_allocator(&self.events);
+        let mut seen = HashSet::new();
 
-        let mut event_id = self.events.first().unwrap().event_id;
         for event in &mut self.events {
-            event.event_id = event_id;
-            event_id += 1;
+            if ! seen.insert(event.event_id) {
+                event.event_id = next_id(event.event_id);
+                seen.insert(event.event_id);
+            }
         }
     }
+
+    /// Sorts events by start time without renumbering
+    pub (crate) fn sort_events(&mut self) {
+        self.events.sort_by_key(|v| v.start);
+        self.last_event_start = match self.events.last() {
+            Some(v) => v.start,
+            None => i64::MIN,
+        };
+    }
+
+    /// Sorts events by start time and renumbers them
+    /// from the event id of the first event
+    pub fn sort(&mut self) {
+        self.sort_events();
+
+        let first = match self.events.first() {
+            Some(v) => v.event_id,
+            None => return,
+        };
+
+        for (i, event) in self.events.iter_mut().enumerate() {
+            event.event_id = first.wrapping_add(i as u16);
+        }
+    }
+
+    /// Removes events outside of the time range
+    pub fn prune(&mut self, range: &Range<i64>) {
+        self.events.retain(|event| event.is_overlap(range));
+        self.last_event_start = match self.events.last() {
+            Some(v) => v.start,
+            None => i64::MIN,
+        };
+    }
+
+    /// Returns copy of the channel with events shifted by the offset in seconds
+    pub fn shift(&self, offset: i64) -> EpgChannel {
+        let events: Vec<EpgEvent> = self.events.iter()
+            .map(|event| EpgEvent {
+                start: event.start.saturating_add(offset),
+                stop: event.stop.map(|v| v.saturating_add(offset)),
+                ..event.clone()
+            })
+            .collect();
+
+        EpgChannel {
+            name: self.name.clone(),
+            last_event_start: events.last().map(|v| v.start).unwrap_or(i64::MIN),
+            events,
+        }
+    }
+
+    /// Returns index of the first event started at the time or after
+    fn lower_bound(&self, time: i64) -> usize {
+        self.events
+            .binary_search_by(|e| if e.start < time { Ordering::Less } else { Ordering::Greater })
+            .unwrap_err()
+    }
+
+    /// Returns index of the first event started after the time
+    fn upper_bound(&self, time: i64) -> usize {
+        self.events
+            .binary_search_by(|e| if e.start <= time { Ordering::Less } else { Ordering::Greater })
+            .unwrap_err()
+    }
+
+    /// Returns event on air at the time.
+    /// If event has no stop time it lasts till the next event
+    pub fn event_at(&self, time: i64) -> Option<&EpgEvent> {
+        let i = self.upper_bound(time);
+        if i == 0 {
+            return None;
+        }
+
+        let event = &self.events[i - 1];
+        let stop = match event.stop {
+            Some(v) if v > event.start => v,
+            _ => self.events.get(i).map(|v| v.start)?,
+        };
+
+        if time < stop { Some(event) } else { None }
+    }
+
+    /// Returns current and next events at the time
+    pub fn now_next(&self, time: i64) -> (Option<&EpgEvent>, Option<&EpgEvent>) {
+        let next = self.events.get(self.upper_bound(time));
+        (self.event_at(time), next)
+    }
+
+    /// Returns events overlapping the time range.
+    /// Overlapping checked with `EpgEvent::is_overlap`
+    pub fn events_range(&self, range: &Range<i64>) -> &[EpgEvent] {
+        let end = self.lower_bound(range.end);
+        let mut begin = self.lower_bound(range.start).min(end);
+        while begin > 0 && self.events[begin - 1].is_overlap(range) {
+            begin -= 1;
+        }
+
+        &self.events[begin .. end]
+    }
 }
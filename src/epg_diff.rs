@@ -0,0 +1,188 @@
+use std::{
+    ops::Range,
+    collections::HashMap,
+};
+
+use crate::{
+    Epg,
+    EpgChannel,
+    EpgEvent,
+};
+
+
+/// Rule to find the same event in both guides
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiffMatch {
+    /// Events with the same `event_id`
+    EventId,
+    /// Events with the same start time
+    Start,
+}
+
+
+/// Event field changed between guides
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventField {
+    EventId,
+    Start,
+    Stop,
+    Title,
+    Subtitle,
+    Desc,
+    Codepage,
+    ParentalRating,
+}
+
+
+/// Modified event
+#[derive(Debug)]
+pub struct EventChange<'a> {
+    pub old: &'a EpgEvent,
+    pub new: &'a EpgEvent,
+    /// Changed fields
+    pub fields: Vec<EventField>,
+}
+
+
+/// Changes in one channel
+#[derive(Default, Debug)]
+pub struct ChannelDiff<'a> {
+    /// Events from the new guide without match in the old guide
+    pub added: Vec<&'a EpgEvent>,
+    /// Events from the old guide without match in the new guide
+    pub removed: Vec<&'a EpgEvent>,
+    pub modified: Vec<EventChange<'a>>,
+}
+
+
+/// Changes between two guides. Contains only changed channels
+#[derive(Default, Debug)]
+pub struct EpgDiff<'a> {
+    pub channels: HashMap<&'a str, ChannelDiff<'a>>,
+}
+
+
+fn event_fields(old: &EpgEvent, new: &EpgEvent) -> Vec<EventField> {
+    let mut fields = Vec::new();
+
+    if old.event_id != new.event_id { fields.push(EventField::EventId) }
+    if old.start != new.start { fields.push(EventField::Start) }
+    if old.stop != new.stop { fields.push(EventField::Stop) }
+    if old.title != new.title { fields.push(EventField::Title) }
+    if old.subtitle != new.subtitle { fields.push(EventField::Subtitle) }
+    if old.desc != new.desc { fields.push(EventField::Desc) }
+    if old.codepage != new.codepage { fields.push(EventField::Codepage) }
+    if old.parental_rating != new.parental_rating { fields.push(EventField::ParentalRating) }
+
+    fields
+}
+
+
+fn event_key(event: &EpgEvent, rule: DiffMatch) -> i64 {
+    match rule {
+        DiffMatch::EventId => i64::from(event.event_id),
+        DiffMatch::Start => event.start,
+    }
+}
+
+
+impl<'a> ChannelDiff<'a> {
+    fn new(old: &'a [EpgEvent], new: &'a [EpgEvent], rule: DiffMatch) -> Self {
+        let mut diff = ChannelDiff::default();
+
+        let mut index = HashMap::new();
+        for (i, event) in old.iter().enumerate() {
+            index.entry(event_key(event, rule)).or_insert(i);
+        }
+
+        let mut matched = vec![false; old.len()];
+
+        for event in new {
+            let i = match index.get(&event_key(event, rule)) {
+                Some(&i) if ! matched[i] => i,
+                _ => {
+                    diff.added.push(event);
+                    continue;
+                }
+            };
+
+            matched[i] = true;
+            let fields = event_fields(&old[i], event);
+            if ! fields.is_empty() {
+                diff.modified.push(EventChange {
+                    old: &old[i],
+                    new: event,
+                    fields,
+                });
+            }
+        }
+
+        for (event, matched) in old.iter().zip(matched) {
+            if ! matched {
+                diff.removed.push(event);
+            }
+        }
+
+        diff
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+
+    /// Returns time range covering all changed events in both guides
+    pub fn range(&self) -> Option<Range<i64>> {
+        let events = self.added.iter()
+            .chain(self.removed.iter())
+            .chain(self.modified.iter().map(|v| &v.old))
+            .chain(self.modified.iter().map(|v| &v.new));
+
+        let mut range: Option<Range<i64>> = None;
+        for event in events {
+            let stop = event.stop.unwrap_or(event.start).max(event.start + 1);
+            range = Some(match range {
+                Some(v) => v.start.min(event.start) .. v.end.max(stop),
+                None => event.start .. stop,
+            });
+        }
+
+        range
+    }
+}
+
+
+impl<'a> EpgDiff<'a> {
+    /// Compares events of the old and the new guide.
+    /// Events of the channel missing in one guide are added or removed
+    pub fn new(old: &'a Epg, new: &'a Epg, rule: DiffMatch) -> Self {
+        let mut diff = EpgDiff::default();
+
+        let empty: &[EpgEvent] = &[];
+        let events = |channel: Option<&'a EpgChannel>| match channel {
+            Some(v) => v.events.as_slice(),
+            None => empty,
+        };
+
+        let ids = new.channels.keys()
+            .chain(old.channels.keys().filter(|id| ! new.channels.contains_key(*id)));
+
+        for id in ids {
+            let channel = ChannelDiff::new(
+                events(old.channels.get(id)),
+                events(new.channels.get(id)),
+                rule);
+
+            if ! channel.is_empty() {
+                diff.channels.insert(id.as_str(), channel);
+            }
+        }
+
+        diff
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty()
+    }
+}
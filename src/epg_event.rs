@@ -1,7 +1,17 @@
-use std::collections::HashMap;
+use std::{
+    convert::TryFrom,
+    ops::Range,
+    collections::HashMap,
+};
 
 use chrono::Utc;
 
+#[cfg(feature = "serde")]
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
 use mpegts::{
     psi::{
         EitItem,
@@ -12,15 +22,52 @@ use mpegts::{
     textcode::StringDVB,
 };
 
+use crate::dvb_time;
+
+
+/// Maximum event duration in EIT: 99:59:59
+pub const EIT_MAX_DURATION: i64 = dvb_time::DVB_DURATION_MAX;
+
 
+#[derive(Debug, Error)]
+pub enum EitError {
+    #[error_kind("Eit: event start out of range: {}", 0)]
+    StartOutOfRange(i64),
+    #[error_kind("Eit: event stop before start")]
+    StopBeforeStart,
+    #[error_kind("Eit: event duration {} exceeds 99:59:59", 0)]
+    DurationOverflow(i64),
+    #[error_kind("Eit: event exceeds section size")]
+    SectionOverflow,
+}
+
+
+/// Guide event. With `serde` feature serialized as:
+///
+/// ```text
+/// {
+///     "event_id": <u16>,
+///     "start": <unix time>,
+///     "stop": <unix time> | null,
+///     "title": { "<lang>": "<text>", ... },
+///     "subtitle": { "<lang>": "<text>", ... },
+///     "desc": { "<lang>": "<text>", ... },
+///     "codepage": <u8>,
+///     "parental_rating": { "<country code>": <age>, ... }
+/// }
+/// ```
+///
+/// `<lang>` is ISO 639-2 language code, `<country code>` is 3-letter
+/// country code. Missing fields are filled with default values
 #[derive(Default, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
 pub struct EpgEvent {
     /// Unique event identifier
     pub event_id: u16,
-    /// Event start time
-    pub start: u64,
-    /// Event stop tiem (equal to the next event start time)
-    pub stop: u64,
+    /// Event start time. UNIX time, negative before 1970
+    pub start: i64,
+    /// Event stop time (equal to the next event start time). None if not defined
+    pub stop: Option<i64>,
     /// Event title list
     pub title: HashMap<String, String>,
     /// Event short description list
@@ -30,84 +77,68 @@ pub struct EpgEvent {
     /// Codepage
     pub codepage: u8,
     /// Parental Rating
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_util::parental_rating"))]
     pub parental_rating: HashMap<[u8; 3], u8>,
 }
 
 
-impl<'a> From<&'a EitItem> for EpgEvent {
-    fn from(eit_item: &EitItem) -> Self {
-        let mut event = EpgEvent {
-            event_id: eit_item.event_id,
-            start: eit_item.start,
-            stop: eit_item.start + u64::from(eit_item.duration),
-            ..Default::default()
-        };
-
-        for desc in eit_item.descriptors.iter() {
-            match desc.tag() {
-                0x4D => {
-                    let v = desc.downcast_ref::<Desc4D>();
-                    event.title.insert(v.lang.to_string(), v.name.to_string());
+impl EpgEvent {
+    /// Returns true if event overlaps the time range.
+    /// Event without stop time is checked by its start time
+    pub fn is_overlap(&self, range: &Range<i64>) -> bool {
+        self.start < range.end &&
+            (self.start >= range.start || matches!(self.stop, Some(v) if v > range.start))
+    }
 
-                    if ! v.text.is_empty() {
-                        event.subtitle
-                            .entry(v.lang.to_string())
-                            .or_insert_with(String::new)
-                            .push_str(&v.text.to_string());
-                    }
-                },
-                0x4E => {
-                    let v = desc.downcast_ref::<Desc4E>();
-                    if ! v.text.is_empty() {
-                        event.desc
-                            .entry(v.lang.to_string())
-                            .or_insert_with(String::new)
-                            .push_str(&v.text.to_string());
-                    }
-                },
-                0x55 => {
-                    // TODO: parental_rating_descriptor
-                },
-                _ => (),
-            };
+    /// Converts event to EIT item. Running status defined at the time.
+    /// Event without stop time has undefined duration `u32::MAX`,
+    /// encoded by `EitSection` with all bits set
+    pub fn eit_item(&self, time: i64) -> Result<EitItem, EitError> {
+        /* EitItem keeps start as unsigned UNIX time */
+        if self.start < 0 || dvb_time::check_time(self.start).is_err() {
+            return Err(EitError::StartOutOfRange(self.start));
         }
 
-        event
-    }
-}
-
+        /* event without stop time has undefined duration */
+        let duration = match self.stop {
+            None => u32::MAX,
+            Some(v) if v < self.start => return Err(EitError::StopBeforeStart),
+            Some(v) => {
+                let duration = v - self.start;
+                if dvb_time::check_duration(duration).is_err() {
+                    return Err(EitError::DurationOverflow(duration));
+                }
+                duration as u32
+            }
+        };
 
-impl<'a> From<&'a EpgEvent> for EitItem {
-    fn from(event: &EpgEvent) -> Self {
         let mut eit_item = EitItem {
-            event_id: event.event_id,
-            start: event.start,
-            duration: (event.stop - event.start) as u32,
+            event_id: self.event_id,
+            start: self.start as u64,
+            duration,
             ..Default::default()
         };
 
-        let current_time = Utc::now().timestamp() as u64;
-        if current_time >= event.start && current_time < event.stop {
-            eit_item.status = 4;
-        } else {
-            eit_item.status = 1;
-        }
+        eit_item.status = match self.stop {
+            Some(v) if time >= self.start && time < v => 4,
+            _ => 1,
+        };
 
-        for (lang, title) in &event.title {
-            let subtitle = match event.subtitle.get(lang) {
+        for (lang, title) in &self.title {
+            let subtitle = match self.subtitle.get(lang) {
                 Some(v) => v,
                 None => "",
             };
 
             eit_item.descriptors.push(Desc4D {
                 lang: StringDVB::from_str(lang, 0),
-                name: StringDVB::from_str(title, event.codepage),
-                text: StringDVB::from_str(subtitle, event.codepage),
+                name: StringDVB::from_str(title, self.codepage),
+                text: StringDVB::from_str(subtitle, self.codepage),
             });
         }
 
-        for (lang, desc) in &event.desc {
-            let mut text_list = StringDVB::from_str(desc, event.codepage);
+        for (lang, desc) in &self.desc {
+            let mut text_list = StringDVB::from_str(desc, self.codepage);
             text_list.truncate(1000);
             let mut text_list = text_list.split(0xF0);
             let mut number: u8 = 0;
@@ -126,10 +157,10 @@ impl<'a> From<&'a EpgEvent> for EitItem {
             }
         }
 
-        if ! event.parental_rating.is_empty() {
+        if ! self.parental_rating.is_empty() {
             let mut desc: Vec<u8> = Vec::default();
 
-            for (country, rating) in &event.parental_rating {
+            for (country, rating) in &self.parental_rating {
                 if (4 ..= 18).contains(rating) {
                     desc.extend_from_slice(country);
                     desc.push(*rating - 3);
@@ -144,6 +175,79 @@ impl<'a> From<&'a EpgEvent> for EitItem {
             }
         }
 
-        eit_item
+        Ok(eit_item)
+    }
+}
+
+
+impl TryFrom<&EitItem> for EpgEvent {
+    type Error = EitError;
+
+    fn try_from(eit_item: &EitItem) -> Result<Self, EitError> {
+        /* undefined start decoded by EitSection as u64::MAX */
+        let start = i64::try_from(eit_item.start).unwrap_or(i64::MAX);
+        if dvb_time::check_time(start).is_err() {
+            return Err(EitError::StartOutOfRange(start));
+        }
+
+        /* undefined duration decoded by EitSection as u32::MAX */
+        let stop = match eit_item.duration {
+            u32::MAX => None,
+            v => {
+                let duration = i64::from(v);
+                if dvb_time::check_duration(duration).is_err() {
+                    return Err(EitError::DurationOverflow(duration));
+                }
+                Some(start + duration)
+            }
+        };
+
+        let mut event = EpgEvent {
+            event_id: eit_item.event_id,
+            start,
+            stop,
+            ..Default::default()
+        };
+
+        for desc in eit_item.descriptors.iter() {
+            match desc.tag() {
+                0x4D => {
+                    let v = desc.downcast_ref::<Desc4D>();
+                    event.title.insert(v.lang.to_string(), v.name.to_string());
+
+                    if ! v.text.is_empty() {
+                        event.subtitle
+                            .entry(v.lang.to_string())
+                            .or_default()
+                            .push_str(&v.text.to_string());
+                    }
+                },
+                0x4E => {
+                    let v = desc.downcast_ref::<Desc4E>();
+                    if ! v.text.is_empty() {
+                        event.desc
+                            .entry(v.lang.to_string())
+                            .or_default()
+                            .push_str(&v.text.to_string());
+                    }
+                },
+                0x55 => {
+                    // TODO: parental_rating_descriptor
+                },
+                _ => (),
+            };
+        }
+
+        Ok(event)
+    }
+}
+
+
+impl TryFrom<&EpgEvent> for EitItem {
+    type Error = EitError;
+
+    #[inline]
+    fn try_from(event: &EpgEvent) -> Result<Self, EitError> {
+        event.eit_item(Utc::now().timestamp())
     }
 }
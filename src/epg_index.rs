@@ -0,0 +1,134 @@
+use std::{
+    ops::Range,
+    collections::{
+        BTreeSet,
+        HashMap,
+        HashSet,
+    },
+};
+
+use unicode_normalization::{
+    UnicodeNormalization,
+    char::is_combining_mark,
+};
+
+use crate::{
+    Epg,
+    EpgEvent,
+};
+
+
+/// Folds case and diacritics and splits text into words
+fn tokenize(text: &str) -> Vec<String> {
+    let mut folded = String::with_capacity(text.len());
+
+    for c in text.nfd() {
+        if is_combining_mark(c) {
+            continue;
+        }
+
+        /* letters without decomposition */
+        match c {
+            'ł' | 'Ł' => folded.push('l'),
+            'ø' | 'Ø' => folded.push('o'),
+            'đ' | 'Đ' => folded.push('d'),
+            'ß' => folded.push_str("ss"),
+            c => folded.extend(c.to_lowercase()),
+        };
+    }
+
+    folded
+        .split(|c: char| ! c.is_alphanumeric())
+        .filter(|v| ! v.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+
+/// Search filter
+#[derive(Default, Debug, Clone)]
+pub struct SearchFilter {
+    /// Search in listed channels only. Empty list for all channels
+    pub channels: HashSet<String>,
+    /// Search events overlapping the time range
+    pub range: Option<Range<i64>>,
+}
+
+
+/// Full-text index over event title, subtitle and description in all languages
+pub struct EpgIndex<'a> {
+    channels: Vec<(&'a str, &'a [EpgEvent])>,
+    /// Word to the list of channel and event index
+    words: HashMap<String, BTreeSet<(usize, usize)>>,
+}
+
+
+impl<'a> EpgIndex<'a> {
+    pub fn new(epg: &'a Epg) -> Self {
+        let mut index = EpgIndex {
+            channels: Vec::with_capacity(epg.channels.len()),
+            words: HashMap::new(),
+        };
+
+        for (id, channel) in &epg.channels {
+            let channel_index = index.channels.len();
+            index.channels.push((id.as_str(), channel.events.as_slice()));
+
+            for (event_index, event) in channel.events.iter().enumerate() {
+                let text = event.title.values()
+                    .chain(event.subtitle.values())
+                    .chain(event.desc.values());
+
+                for value in text {
+                    for word in tokenize(value) {
+                        index.words
+                            .entry(word)
+                            .or_default()
+                            .insert((channel_index, event_index));
+                    }
+                }
+            }
+        }
+
+        index
+    }
+
+    /// Returns events contains all words from the query.
+    /// Result is ordered by event start time
+    pub fn search(&self, query: &str, filter: &SearchFilter) -> Vec<(&'a str, &'a EpgEvent)> {
+        let mut found: Option<BTreeSet<(usize, usize)>> = None;
+
+        for word in tokenize(query) {
+            let list = match self.words.get(&word) {
+                Some(v) => v,
+                None => return Vec::new(),
+            };
+
+            found = Some(match found {
+                Some(v) => v.intersection(list).cloned().collect(),
+                None => list.clone(),
+            });
+        }
+
+        let mut result: Vec<(&'a str, &'a EpgEvent)> = found
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(channel, event)| {
+                let (id, events) = self.channels[channel];
+                (id, &events[event])
+            })
+            .filter(|(id, event)| {
+                if ! filter.channels.is_empty() && ! filter.channels.contains(*id) {
+                    return false;
+                }
+                match &filter.range {
+                    Some(range) => event.is_overlap(range),
+                    None => true,
+                }
+            })
+            .collect();
+
+        result.sort_by(|a, b| a.1.start.cmp(&b.1.start).then(a.0.cmp(b.0)));
+        result
+    }
+}
@@ -0,0 +1,72 @@
+use std::{
+    ops::Range,
+    collections::{
+        HashMap,
+        HashSet,
+    },
+};
+
+#[cfg(feature = "tz")]
+use chrono_tz::Tz;
+
+#[cfg(feature = "tz")]
+use crate::DstPolicy;
+
+
+#[derive(Default, Debug, Clone)]
+pub struct EpgOptions {
+    /// Reject invalid XMLTV instead of skipping broken elements:
+    /// unknown root element, duplicate channel id, programme for
+    /// undeclared channel, missing required attribute, or bad date
+    pub strict: bool,
+    /// Keep events for channels that are never declared in the document.
+    /// Such channels are created without names
+    pub keep_undeclared: bool,
+    /// Load only channels with listed ids. Empty list allows all channels
+    pub allow: HashSet<String>,
+    /// Skip channels with listed ids
+    pub deny: HashSet<String>,
+    /// Channel id mapping: source id to the service id.
+    /// Filters are checked with the source id
+    pub map: HashMap<String, String>,
+    /// Keep only events overlapping the time range (Unix timestamps)
+    pub window: Option<Range<i64>>,
+    /// Entry name to read from zip archive. By default first .xml entry
+    pub archive_entry: Option<String>,
+    /// Time zone for dates without offset. By default dates are in UTC
+    #[cfg(feature = "tz")]
+    pub timezone: Option<Tz>,
+    /// Time zone for dates without offset by channel id (after mapping).
+    /// Overrides `timezone`
+    #[cfg(feature = "tz")]
+    pub channel_timezone: HashMap<String, Tz>,
+    /// Resolution of local times at daylight saving time transition
+    #[cfg(feature = "tz")]
+    pub dst_policy: DstPolicy,
+}
+
+
+impl EpgOptions {
+    /// Applies channel filters and mapping to the source channel id.
+    /// Returns None if channel should be skipped
+    pub (crate) fn channel_id(&self, id: String) -> Option<String> {
+        if ! self.allow.is_empty() && ! self.allow.contains(&id) {
+            return None;
+        }
+
+        if self.deny.contains(&id) {
+            return None;
+        }
+
+        match self.map.get(&id) {
+            Some(v) => Some(v.clone()),
+            None => Some(id),
+        }
+    }
+
+    /// Returns time zone for dates without offset
+    #[cfg(feature = "tz")]
+    pub (crate) fn timezone(&self, id: &str) -> Option<Tz> {
+        self.channel_timezone.get(id).cloned().or(self.timezone)
+    }
+}
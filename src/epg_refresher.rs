@@ -0,0 +1,201 @@
+use std::{
+    thread,
+    time::Duration,
+    sync::{
+        Arc,
+        RwLock,
+        mpsc,
+    },
+};
+
+use crate::{
+    Epg,
+    EpgAggregator,
+    EpgError,
+};
+
+
+#[derive(Debug, Error)]
+pub enum RefreshError {
+    #[error_kind("Refresh: source #{}: {}", 0, 1)]
+    Source(usize, EpgError),
+    #[error_kind("Refresh: invalid guide: {}", 0)]
+    Invalid(String),
+}
+
+
+/// Shared access to the latest published guide
+#[derive(Default, Debug, Clone)]
+pub struct EpgHandle {
+    inner: Arc<RwLock<Arc<Epg>>>,
+}
+
+
+impl EpgHandle {
+    /// Returns latest published guide.
+    /// Guide stays valid while it used even if the new one published
+    pub fn get(&self) -> Arc<Epg> {
+        self.inner.read().unwrap().clone()
+    }
+
+    /// Replaces guide for all readers
+    pub fn publish(&self, epg: Epg) {
+        *self.inner.write().unwrap() = Arc::new(epg);
+    }
+}
+
+
+type Validate = Box<dyn Fn(&Epg) -> Result<(), String> + Send>;
+type OnSuccess = Box<dyn Fn(&Epg) + Send>;
+type OnFailure = Box<dyn Fn(&RefreshError) + Send>;
+
+
+/// Periodically reloads sources into a fresh `Epg` and publishes it.
+/// On failure readers keep the last good guide
+pub struct EpgRefresher {
+    /// Sources to reload
+    pub aggregator: EpgAggregator,
+    /// Delay between refreshes
+    pub interval: Duration,
+    /// Publish guide if some sources failed
+    pub allow_partial: bool,
+    handle: EpgHandle,
+    validate: Option<Validate>,
+    on_success: Option<OnSuccess>,
+    on_failure: Option<OnFailure>,
+}
+
+
+impl EpgRefresher {
+    pub fn new(aggregator: EpgAggregator, interval: Duration) -> Self {
+        EpgRefresher {
+            aggregator,
+            interval,
+            allow_partial: false,
+            handle: EpgHandle::default(),
+            validate: None,
+            on_success: None,
+            on_failure: None,
+        }
+    }
+
+    /// Returns handle to read published guide
+    pub fn handle(&self) -> EpgHandle {
+        self.handle.clone()
+    }
+
+    /// Sets validation for the loaded guide.
+    /// By default guide without channels is rejected
+    pub fn validate<F>(&mut self, f: F)
+    where
+        F: Fn(&Epg) -> Result<(), String> + Send + 'static,
+    {
+        self.validate = Some(Box::new(f));
+    }
+
+    /// Sets callback called after new guide published
+    pub fn on_success<F>(&mut self, f: F)
+    where
+        F: Fn(&Epg) + Send + 'static,
+    {
+        self.on_success = Some(Box::new(f));
+    }
+
+    /// Sets callback called if refresh failed
+    pub fn on_failure<F>(&mut self, f: F)
+    where
+        F: Fn(&RefreshError) + Send + 'static,
+    {
+        self.on_failure = Some(Box::new(f));
+    }
+
+    fn load(&self) -> Result<Epg, RefreshError> {
+        let result = self.aggregator.load();
+
+        if ! self.allow_partial {
+            if let Some((index, e)) = result.errors.into_iter().next() {
+                return Err(RefreshError::Source(index, e));
+            }
+        }
+
+        let epg = result.epg;
+        match &self.validate {
+            Some(validate) => validate(&epg).map_err(RefreshError::Invalid)?,
+            None if epg.channels.is_empty() => {
+                return Err(RefreshError::Invalid("no channels".to_owned()));
+            }
+            None => {},
+        };
+
+        Ok(epg)
+    }
+
+    /// Reloads sources and publishes new guide
+    pub fn refresh(&self) -> Result<(), RefreshError> {
+        match self.load() {
+            Ok(epg) => {
+                self.handle.publish(epg);
+                if let Some(f) = &self.on_success {
+                    f(&self.handle.get());
+                }
+                Ok(())
+            }
+            Err(e) => {
+                if let Some(f) = &self.on_failure {
+                    f(&e);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Starts refresh in the background thread.
+    /// First refresh starts immediately
+    pub fn start(self) -> EpgRefreshThread {
+        let handle = self.handle();
+        let (tx, rx) = mpsc::channel();
+
+        let thread = thread::spawn(move || loop {
+            self.refresh().ok();
+            match rx.recv_timeout(self.interval) {
+                Err(mpsc::RecvTimeoutError::Timeout) => {},
+                _ => break,
+            };
+        });
+
+        EpgRefreshThread {
+            handle,
+            stop: tx,
+            thread: Some(thread),
+        }
+    }
+}
+
+
+/// Background refresh thread. Stops on drop
+pub struct EpgRefreshThread {
+    handle: EpgHandle,
+    stop: mpsc::Sender<()>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+
+impl EpgRefreshThread {
+    /// Returns handle to read published guide
+    pub fn handle(&self) -> EpgHandle {
+        self.handle.clone()
+    }
+
+    /// Stops refresh and waits for the thread
+    pub fn stop(self) {}
+}
+
+
+impl Drop for EpgRefreshThread {
+    fn drop(&mut self) {
+        self.stop.send(()).ok();
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    fs::{
+        self,
+        File,
+    },
+    io::{
+        self,
+        BufRead,
+        BufReader,
+        Write,
+    },
+    path::PathBuf,
+};
+
+use crate::{
+    EpgError,
+    EpgSource,
+    LoadPolicy,
+};
+
+
+/// FNV-1a hash of the URL. Used as cache file name
+fn cache_key(url: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in url.bytes() {
+        hash ^= u64::from(b);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+
+/// Validators of the cached response
+#[derive(Default, Debug)]
+struct CacheMeta {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+
+impl CacheMeta {
+    fn load(path: &PathBuf) -> CacheMeta {
+        let mut meta = CacheMeta::default();
+
+        let data = match fs::read_to_string(path) {
+            Ok(v) => v,
+            Err(_) => return meta,
+        };
+
+        for line in data.lines() {
+            let mut i = line.splitn(2, ": ");
+            match (i.next(), i.next()) {
+                (Some("etag"), Some(v)) => meta.etag = Some(v.to_owned()),
+                (Some("last-modified"), Some(v)) => meta.last_modified = Some(v.to_owned()),
+                _ => {},
+            };
+        }
+
+        meta
+    }
+
+    fn save(&self, path: &PathBuf, url: &str) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        writeln!(file, "url: {}", url)?;
+        if let Some(v) = &self.etag {
+            writeln!(file, "etag: {}", v)?;
+        }
+        if let Some(v) = &self.last_modified {
+            writeln!(file, "last-modified: {}", v)?;
+        }
+        Ok(())
+    }
+}
+
+
+/// On-disk cache for remote sources.
+///
+/// Stores response body with ETag and Last-Modified in the cache directory
+/// and sends conditional request on the next load. If source is not modified
+/// `open` returns cached copy and `open_modified` returns None, so caller
+/// may skip reparsing with `Epg::load_modified`.
+/// Register it with `EpgSources::register_http` in `Epg::sources`
+#[derive(Debug, Clone)]
+pub struct HttpCache {
+    dir: PathBuf,
+    /// Timeouts, retries, redirects and response size limit
+    pub policy: LoadPolicy,
+}
+
+
+impl HttpCache {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        HttpCache {
+            dir: dir.into(),
+            policy: LoadPolicy::default(),
+        }
+    }
+
+    fn path(&self, url: &str, ext: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", cache_key(url), ext))
+    }
+
+    /// Opens cached response body without request to the server
+    pub fn cached(&self, url: &str) -> Result<Box<dyn BufRead>, EpgError> {
+        let file = File::open(self.path(url, "body"))?;
+        Ok(Box::new(BufReader::new(file)))
+    }
+
+    /// Removes cached response
+    pub fn remove(&self, url: &str) -> Result<(), EpgError> {
+        for ext in &["meta", "body"] {
+            match fs::remove_file(self.path(url, ext)) {
+                Err(ref e) if e.kind() == io::ErrorKind::NotFound => {},
+                v => v?,
+            };
+        }
+        Ok(())
+    }
+}
+
+
+impl HttpCache {
+    /// Sends conditional request and updates cached copy.
+    /// Returns false if source is not modified
+    fn update(&self, url: &str) -> Result<bool, EpgError> {
+        let meta_path = self.path(url, "meta");
+        let body_path = self.path(url, "body");
+
+        /* send validators only if response body is cached */
+        let meta = if body_path.exists() {
+            CacheMeta::load(&meta_path)
+        } else {
+            CacheMeta::default()
+        };
+
+        let mut headers = Vec::new();
+        if let Some(v) = &meta.etag {
+            headers.push(("if-none-match", v.as_str()));
+        }
+        if let Some(v) = &meta.last_modified {
+            headers.push(("if-modified-since", v.as_str()));
+        }
+
+        fs::create_dir_all(&self.dir)?;
+
+        /* keep previous cache if download fails */
+        let tmp_path = self.path(url, "tmp");
+        let client = match self.policy.download(url, &headers, &tmp_path) {
+            Ok(v) => v,
+            Err(e) => {
+                fs::remove_file(&tmp_path).ok();
+                return Err(e);
+            }
+        };
+
+        match client.response.code {
+            200 => {},
+            304 => return Ok(false),
+            v => return Err(EpgError::HttpStatus(v)),
+        };
+
+        let meta = CacheMeta {
+            etag: client.response.header.get("etag").map(str::to_owned),
+            last_modified: client.response.header.get("last-modified").map(str::to_owned),
+        };
+
+        fs::rename(&tmp_path, &body_path)?;
+        meta.save(&meta_path, url)?;
+
+        Ok(true)
+    }
+}
+
+
+impl EpgSource for HttpCache {
+    fn open(&self, url: &str) -> Result<Box<dyn BufRead>, EpgError> {
+        self.update(url)?;
+        self.cached(url)
+    }
+
+    fn open_modified(&self, url: &str) -> Result<Option<Box<dyn BufRead>>, EpgError> {
+        if self.update(url)? {
+            self.cached(url).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+}
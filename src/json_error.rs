@@ -0,0 +1,53 @@
+//! JSON reader error. Defined without `json` feature to keep
+//! `EpgError::Json` variant in all builds
+
+use std::{
+    error,
+    fmt,
+};
+
+
+#[derive(Debug)]
+pub enum JsonReaderError {
+    /// Syntax error or unexpected structure
+    #[cfg(feature = "json")]
+    Json(serde_json::Error),
+    /// Required field missing in the strict mode: object and field name
+    MissingField(&'static str, &'static str),
+    /// Invalid date in the strict mode
+    InvalidDate(String),
+}
+
+
+impl fmt::Display for JsonReaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            #[cfg(feature = "json")]
+            JsonReaderError::Json(e) => write!(f, "JsonReader: {}", e),
+            JsonReaderError::MissingField(object, field) => {
+                write!(f, "JsonReader: {} without {}", object, field)
+            }
+            JsonReaderError::InvalidDate(v) => write!(f, "JsonReader: invalid date: {}", v),
+        }
+    }
+}
+
+
+impl error::Error for JsonReaderError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "json")]
+            JsonReaderError::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+
+#[cfg(feature = "json")]
+impl From<serde_json::Error> for JsonReaderError {
+    #[inline]
+    fn from(e: serde_json::Error) -> Self {
+        JsonReaderError::Json(e)
+    }
+}
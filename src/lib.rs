@@ -3,17 +3,144 @@ extern crate error_rules;
 
 mod read_xml;
 mod write_xml;
+#[cfg(feature = "json")]
+mod read_json;
+mod json_error;
+pub use crate::json_error::JsonReaderError;
+#[cfg(feature = "json")]
+mod write_json;
+#[cfg(feature = "serde")]
+mod serde_util;
+
+mod compression;
+pub use crate::compression::Compression;
+
+mod snapshot;
+pub use crate::snapshot::{
+    SnapshotError,
+    SNAPSHOT_VERSION,
+};
+
+mod dvb_time;
+pub use crate::dvb_time::{
+    check_duration,
+    check_time,
+    decode_duration,
+    decode_time,
+    encode_duration,
+    encode_time,
+    mjd_to_time,
+    time_to_mjd,
+    DvbTimeError,
+    DVB_DURATION_MAX,
+    DVB_TIME_MAX,
+    DVB_TIME_MIN,
+};
 
 mod epg_event;
-pub use crate::epg_event::EpgEvent;
+pub use crate::epg_event::{
+    EitError,
+    EpgEvent,
+    EIT_MAX_DURATION,
+};
 
 mod epg_channel;
 pub use crate::epg_channel::EpgChannel;
 
+#[cfg(feature = "tz")]
+mod local_time;
+#[cfg(feature = "tz")]
+pub use crate::local_time::{
+    DstPolicy,
+    LocalTimeDiagnostic,
+    LocalTimeIssue,
+};
+
+mod timeshift;
+pub use crate::timeshift::Timeshift;
+
+mod epg_options;
+pub use crate::epg_options::EpgOptions;
+
+mod load_policy;
+pub use crate::load_policy::LoadPolicy;
+
+mod source;
+pub use crate::source::{
+    split_url,
+    EpgSource,
+    EpgSources,
+    FileSource,
+    HttpSource,
+};
+
+mod http_cache;
+pub use crate::http_cache::HttpCache;
+
+mod service_map;
+pub use crate::service_map::{
+    DvbService,
+    ServiceMap,
+    ServiceMapError,
+};
+
 mod epg;
 pub use crate::epg::{
     Epg,
     EpgError,
+    EpgFormat,
+    UploadMethod,
+    WriteOptions,
+};
+
+mod eit_section;
+pub use crate::eit_section::EitSection;
+
+mod eit_generator;
+pub use crate::eit_generator::{
+    EitGenerator,
+    EIT_PF_ACTUAL,
+    EIT_PF_OTHER,
+    EIT_SCHEDULE_ACTUAL,
+    EIT_SCHEDULE_OTHER,
+};
+
+mod eit_carousel;
+pub use crate::eit_carousel::{
+    EitCarousel,
+    EIT_PID,
+};
+
+mod epg_diff;
+pub use crate::epg_diff::{
+    ChannelDiff,
+    DiffMatch,
+    EpgDiff,
+    EventChange,
+    EventField,
+};
+
+#[cfg(feature = "search")]
+mod epg_index;
+#[cfg(feature = "search")]
+pub use crate::epg_index::{
+    EpgIndex,
+    SearchFilter,
+};
+
+mod epg_aggregator;
+pub use crate::epg_aggregator::{
+    Aggregated,
+    AggregatorSource,
+    EpgAggregator,
+};
+
+mod epg_refresher;
+pub use crate::epg_refresher::{
+    EpgHandle,
+    EpgRefresher,
+    EpgRefreshThread,
+    RefreshError,
 };
 
 
@@ -0,0 +1,364 @@
+use std::{
+    fs::{
+        self,
+        File,
+    },
+    io::{
+        self,
+        BufRead,
+        BufReader,
+        Read,
+        Write,
+    },
+    path::{
+        Path,
+        PathBuf,
+    },
+    process,
+    sync::atomic::{
+        AtomicUsize,
+        Ordering,
+    },
+    thread,
+    time::Duration,
+};
+
+use http::HttpClient;
+
+use crate::EpgError;
+
+
+/// Network policy for remote sources
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadPolicy {
+    /// Connection timeout
+    pub connect_timeout: Option<Duration>,
+    /// Socket read timeout
+    pub read_timeout: Option<Duration>,
+    /// Number of retries after failed request.
+    /// Request failed on connection error, transfer error or server error (5xx).
+    /// Local file errors are not retried, POST upload is never retried
+    pub retries: usize,
+    /// Delay before first retry. Doubles on each next retry
+    pub backoff: Duration,
+    /// Maximum delay between retries
+    pub backoff_max: Duration,
+    /// Maximum number of redirects to follow
+    pub max_redirects: usize,
+    /// Maximum response size in bytes
+    pub max_size: Option<u64>,
+}
+
+
+impl Default for LoadPolicy {
+    fn default() -> Self {
+        LoadPolicy {
+            connect_timeout: Some(Duration::from_secs(10)),
+            read_timeout: Some(Duration::from_secs(30)),
+            retries: 3,
+            backoff: Duration::from_secs(1),
+            backoff_max: Duration::from_secs(60),
+            max_redirects: 5,
+            max_size: None,
+        }
+    }
+}
+
+
+/// Returns true for I/O errors of the network transfer.
+/// Local file errors like permission denied are not transient
+fn is_transient(e: &io::Error) -> bool {
+    matches!(e.kind(),
+        io::ErrorKind::ConnectionRefused |
+        io::ErrorKind::ConnectionReset |
+        io::ErrorKind::ConnectionAborted |
+        io::ErrorKind::NotConnected |
+        io::ErrorKind::BrokenPipe |
+        io::ErrorKind::TimedOut |
+        io::ErrorKind::WouldBlock |
+        io::ErrorKind::Interrupted |
+        io::ErrorKind::UnexpectedEof)
+}
+
+
+/// Returns error for server errors (5xx)
+fn check_status(client: HttpClient) -> Result<HttpClient, EpgError> {
+    match client.response.code {
+        v if v >= 500 => Err(EpgError::HttpStatus(v)),
+        _ => Ok(client),
+    }
+}
+
+
+/// Resolves redirect location relative to the request URL
+fn redirect_url(url: &str, location: &str) -> String {
+    if location.contains("://") {
+        return location.to_owned();
+    }
+
+    let skip = url.find("://").map(|i| i + 3).unwrap_or(0);
+    let origin = match url[skip ..].find('/') {
+        Some(i) => &url[.. skip + i],
+        None => url,
+    };
+
+    if location.starts_with('/') {
+        format!("{}{}", origin, location)
+    } else {
+        let base = match url[skip ..].rfind('/') {
+            Some(i) => &url[.. skip + i],
+            None => origin,
+        };
+        format!("{}/{}", base, location)
+    }
+}
+
+
+impl LoadPolicy {
+    fn delay(&self, attempt: usize) -> Duration {
+        let mut delay = self.backoff;
+        for _ in 0 .. attempt {
+            delay *= 2;
+            if delay >= self.backoff_max {
+                return self.backoff_max;
+            }
+        }
+        delay
+    }
+
+    fn client(&self, url: &str, headers: &[(&str, &str)]) -> Result<HttpClient, EpgError> {
+        let mut client = HttpClient::new(url)?;
+        if let Some(v) = self.connect_timeout {
+            client.set_connect_timeout(v);
+        }
+        if let Some(v) = self.read_timeout {
+            client.set_read_timeout(v);
+        }
+        for (key, value) in headers {
+            client.request.header.set(*key, *value);
+        }
+        Ok(client)
+    }
+
+    fn request(&self, url: &str, headers: &[(&str, &str)]) -> Result<HttpClient, EpgError> {
+        let mut client = self.client(url, headers)?;
+        client.get()?;
+        Ok(client)
+    }
+
+    fn send(&self, url: &str, method: &str, headers: &[(&str, &str)], body: &[u8]) -> Result<HttpClient, EpgError> {
+        let mut client = self.client(url, headers)?;
+        client.request.set_method(method);
+        client.request.header.set("content-length", body.len());
+        client.send()?;
+        client.write_all(body)?;
+        client.flush()?;
+        client.receive()?;
+        check_status(client)
+    }
+
+    /// Calls `f` until it succeeds. Retries on connection errors,
+    /// network transfer errors and server errors (5xx)
+    fn retry<T, F>(&self, mut f: F) -> Result<T, EpgError>
+    where
+        F: FnMut() -> Result<T, EpgError>,
+    {
+        let mut attempt = 0;
+
+        loop {
+            let result = f();
+            let retry = match &result {
+                Err(EpgError::HttpClient(_)) => true,
+                Err(EpgError::Io(e)) => is_transient(e),
+                Err(EpgError::HttpStatus(v)) => *v >= 500,
+                _ => false,
+            };
+
+            if ! retry || attempt >= self.retries {
+                return result;
+            }
+
+            thread::sleep(self.delay(attempt));
+            attempt += 1;
+        }
+    }
+
+    /// Sends request with body. Redirects are not followed.
+    /// POST is not idempotent and sent once
+    pub (crate) fn upload(&self, url: &str, method: &str, headers: &[(&str, &str)], body: &[u8]) -> Result<HttpClient, EpgError> {
+        if method == "POST" {
+            return self.send(url, method, headers, body);
+        }
+        self.retry(|| self.send(url, method, headers, body))
+    }
+
+    /// Sends GET request and saves response body to the file.
+    /// Body saved only with status 200, other responses returned as is.
+    /// Request repeated if body transfer failed.
+    /// Returns client with response status and headers
+    pub (crate) fn download(&self, url: &str, headers: &[(&str, &str)], path: &Path) -> Result<HttpClient, EpgError> {
+        self.retry(|| {
+            let client = check_status(self.follow(url, headers)?)?;
+            if client.response.code != 200 {
+                return Ok(client);
+            }
+
+            let mut file = File::create(path)?;
+            let mut body = self.limit(client);
+            if let Err(e) = io::copy(&mut body, &mut file) {
+                return Err(if body.exceeded { EpgError::TooLarge } else { e.into() });
+            }
+            file.sync_all()?;
+
+            Ok(body.inner)
+        })
+    }
+
+    fn follow(&self, url: &str, headers: &[(&str, &str)]) -> Result<HttpClient, EpgError> {
+        let mut url = url.to_owned();
+        let mut redirects = 0;
+
+        loop {
+            let client = self.request(&url, headers)?;
+            match client.response.code {
+                301 | 302 | 303 | 307 | 308 => {},
+                _ => return Ok(client),
+            };
+
+            let location = match client.response.header.get("location") {
+                Some(v) => redirect_url(&url, v),
+                None => return Ok(client),
+            };
+
+            if redirects >= self.max_redirects {
+                return Err(EpgError::TooManyRedirects);
+            }
+
+            redirects += 1;
+            url = location;
+        }
+    }
+
+    /// Limits response size
+    fn limit<R: BufRead>(&self, inner: R) -> SizeLimit<R> {
+        SizeLimit {
+            inner,
+            remain: self.max_size,
+            exceeded: false,
+        }
+    }
+}
+
+
+/// Reader with size limit. Returns error if limit exceeded
+struct SizeLimit<R> {
+    inner: R,
+    remain: Option<u64>,
+    exceeded: bool,
+}
+
+
+impl<R> SizeLimit<R> {
+    fn count(&mut self, size: usize) {
+        if let Some(remain) = self.remain {
+            let size = size as u64;
+            if size > remain {
+                self.exceeded = true;
+                self.remain = Some(0);
+            } else {
+                self.remain = Some(remain - size);
+            }
+        }
+    }
+
+    fn check(&self) -> io::Result<()> {
+        if self.exceeded {
+            Err(io::Error::new(io::ErrorKind::InvalidData, "response size limit exceeded"))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+
+impl<R: Read> Read for SizeLimit<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.check()?;
+        let n = self.inner.read(buf)?;
+        self.count(n);
+        self.check()?;
+        Ok(n)
+    }
+}
+
+
+impl<R: BufRead> BufRead for SizeLimit<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.check()?;
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.count(amt);
+        self.inner.consume(amt)
+    }
+}
+
+
+/// Downloaded file in the temporary directory. Removed on drop
+pub (crate) struct TempFile {
+    path: PathBuf,
+    inner: BufReader<File>,
+}
+
+
+impl TempFile {
+    /// Returns unique path in the temporary directory
+    pub (crate) fn path() -> PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let id = COUNTER.fetch_add(1, Ordering::Relaxed);
+        std::env::temp_dir().join(format!("epg-{}-{}.tmp", process::id(), id))
+    }
+
+    /// Opens file. File removed when reader dropped
+    pub (crate) fn open(path: PathBuf) -> io::Result<Self> {
+        match File::open(&path) {
+            Ok(file) => Ok(TempFile {
+                path,
+                inner: BufReader::new(file),
+            }),
+            Err(e) => {
+                fs::remove_file(&path).ok();
+                Err(e)
+            }
+        }
+    }
+}
+
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        fs::remove_file(&self.path).ok();
+    }
+}
+
+
+impl Read for TempFile {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+
+impl BufRead for TempFile {
+    #[inline]
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    #[inline]
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt)
+    }
+}
@@ -0,0 +1,84 @@
+use chrono::{
+    Duration,
+    LocalResult,
+    NaiveDateTime,
+    Offset,
+    TimeZone,
+};
+
+use chrono_tz::Tz;
+
+
+/// Resolution of local time at daylight saving time transition
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DstPolicy {
+    /// Earlier instant: first occurrence of the repeated time,
+    /// or skipped time with offset after transition
+    Earlier,
+    /// Later instant: second occurrence of the repeated time,
+    /// or skipped time with offset before transition
+    Later,
+    /// Time is invalid
+    Reject,
+}
+
+
+/* derive with `#[default]` variant requires Rust 1.62 */
+#[allow(clippy::derivable_impls)]
+impl Default for DstPolicy {
+    fn default() -> Self {
+        DstPolicy::Earlier
+    }
+}
+
+
+/// Local time issue at daylight saving time transition
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LocalTimeIssue {
+    /// Time occurs twice when clocks go back
+    Ambiguous,
+    /// Time skipped when clocks go forward
+    NonExistent,
+}
+
+
+/// Report on local time resolved with `DstPolicy`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalTimeDiagnostic {
+    /// Channel id
+    pub channel: String,
+    /// Date value from the source
+    pub value: String,
+    pub timezone: Tz,
+    pub issue: LocalTimeIssue,
+    /// Resolved UNIX time. None if rejected by policy
+    pub resolved: Option<i64>,
+}
+
+
+/// Converts local time in the time zone to UNIX time.
+/// Returns time and issue if time at daylight saving time transition
+pub (crate) fn resolve(tz: Tz, local: &NaiveDateTime, policy: DstPolicy) -> (Option<i64>, Option<LocalTimeIssue>) {
+    let (earlier, later, issue) = match tz.from_local_datetime(local) {
+        LocalResult::Single(v) => return (Some(v.timestamp()), None),
+        LocalResult::Ambiguous(a, b) => {
+            (a.timestamp(), b.timestamp(), LocalTimeIssue::Ambiguous)
+        }
+        LocalResult::None => {
+            /* offsets around transition. transitions are months apart */
+            let day = Duration::days(1);
+            let before = tz.offset_from_utc_datetime(&(*local - day)).fix().local_minus_utc();
+            let after = tz.offset_from_utc_datetime(&(*local + day)).fix().local_minus_utc();
+            let time = local.timestamp();
+            (time - i64::from(after), time - i64::from(before), LocalTimeIssue::NonExistent)
+        }
+    };
+
+    let time = match policy {
+        DstPolicy::Earlier => Some(earlier.min(later)),
+        DstPolicy::Later => Some(earlier.max(later)),
+        DstPolicy::Reject => None,
+    };
+
+    (time, Some(issue))
+}
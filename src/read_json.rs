@@ -0,0 +1,249 @@
+use std::{
+    fmt,
+    io,
+    collections::HashMap,
+};
+
+use chrono::DateTime;
+
+use serde::{
+    de::{
+        DeserializeSeed,
+        Deserializer,
+        Error as _,
+        IgnoredAny,
+        MapAccess,
+        SeqAccess,
+        Visitor,
+    },
+    Deserialize,
+};
+
+use crate::{
+    Epg,
+    EpgChannel,
+    EpgEvent,
+    JsonReaderError,
+    serde_util::parental_rating,
+};
+
+
+type Result<T> = std::result::Result<T, JsonReaderError>;
+
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct JsonChannel {
+    id: String,
+    name: HashMap<String, String>,
+    events: Vec<JsonEvent>,
+}
+
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct JsonEvent {
+    event_id: u16,
+    start: Option<String>,
+    stop: Option<String>,
+    title: HashMap<String, String>,
+    subtitle: HashMap<String, String>,
+    desc: HashMap<String, String>,
+    #[serde(deserialize_with = "parental_rating::deserialize")]
+    parental_rating: HashMap<[u8; 3], u8>,
+}
+
+
+/// Skips leading whitespace and checks that document starts with `{`
+pub (crate) fn is_json<R: io::BufRead>(src: &mut R) -> io::Result<bool> {
+    loop {
+        let (found, used) = {
+            let available = match src.fill_buf() {
+                Ok(v) => v,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            if available.is_empty() {
+                return Ok(false);
+            }
+
+            match available.iter().position(|b| ! b.is_ascii_whitespace()) {
+                Some(i) => (Some(available[i] == b'{'), i),
+                None => (None, available.len()),
+            }
+        };
+
+        src.consume(used);
+        if let Some(v) = found {
+            return Ok(v);
+        }
+    }
+}
+
+
+/// Parses ISO 8601 date. In the lenient mode invalid date is None
+fn parse_date(value: &str, strict: bool) -> Result<Option<i64>> {
+    match DateTime::parse_from_rfc3339(value) {
+        Ok(v) => Ok(Some(v.timestamp())),
+        Err(_) if strict => Err(JsonReaderError::InvalidDate(value.to_owned())),
+        Err(_) => Ok(None),
+    }
+}
+
+
+/// Merges channel into the guide.
+/// Event without stop time accepted in the strict mode like in the XMLTV reader
+fn push_channel(epg: &mut Epg, channel: JsonChannel) -> Result<()> {
+    let JsonChannel { id, name, events } = channel;
+    let strict = epg.options.strict;
+
+    if id.is_empty() {
+        if strict {
+            return Err(JsonReaderError::MissingField("channel", "id"));
+        }
+        return Ok(());
+    }
+
+    let id = match epg.options.channel_id(id) {
+        Some(v) => v,
+        None => return Ok(()),
+    };
+
+    let channel = epg.channels
+        .entry(id)
+        .or_insert_with(|| EpgChannel {
+            name,
+            ..Default::default()
+        });
+
+    for e in events {
+        /* event without valid start time skipped in the lenient mode */
+        let start = match e.start {
+            Some(v) => parse_date(&v, strict)?,
+            None if strict => return Err(JsonReaderError::MissingField("event", "start")),
+            None => None,
+        };
+        let start = match start {
+            Some(v) => v,
+            None => continue,
+        };
+        let stop = match e.stop {
+            Some(v) => parse_date(&v, strict)?,
+            None => None,
+        };
+
+        let event = EpgEvent {
+            event_id: e.event_id,
+            start,
+            stop,
+            title: e.title,
+            subtitle: e.subtitle,
+            desc: e.desc,
+            parental_rating: e.parental_rating,
+            ..Default::default()
+        };
+
+        if let Some(window) = &epg.options.window {
+            if ! event.is_overlap(window) {
+                continue;
+            }
+        }
+
+        if channel.last_event_start < event.start {
+            channel.events.push(event);
+        }
+    }
+
+    channel.sort();
+    Ok(())
+}
+
+
+/// Guide document. Channels merged one by one while parsing
+struct GuideSeed<'a> {
+    epg: &'a mut Epg,
+    /// Reader error. Parsing stopped with custom serde error
+    error: Option<JsonReaderError>,
+}
+
+
+impl<'a, 'de> DeserializeSeed<'de> for &mut GuideSeed<'a> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, d: D) -> std::result::Result<(), D::Error> {
+        d.deserialize_map(self)
+    }
+}
+
+
+impl<'a, 'de> Visitor<'de> for &mut GuideSeed<'a> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("guide object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "channels" {
+                map.next_value_seed(ChannelsSeed(&mut *self))?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+
+struct ChannelsSeed<'a, 'b>(&'b mut GuideSeed<'a>);
+
+
+impl<'a, 'b, 'de> DeserializeSeed<'de> for ChannelsSeed<'a, 'b> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, d: D) -> std::result::Result<(), D::Error> {
+        d.deserialize_seq(self)
+    }
+}
+
+
+impl<'a, 'b, 'de> Visitor<'de> for ChannelsSeed<'a, 'b> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("channels list")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<(), A::Error> {
+        while let Some(channel) = seq.next_element::<JsonChannel>()? {
+            if let Err(e) = push_channel(self.0.epg, channel) {
+                self.0.error = Some(e);
+                return Err(A::Error::custom("stopped"));
+            }
+        }
+        Ok(())
+    }
+}
+
+
+/// Reads guide in the JSON format. See `write_json` for details.
+/// Channels and events merged into the guide like in the XMLTV reader.
+/// Document parsed as a stream: only one channel kept in memory
+pub fn read_json<R: io::BufRead>(
+    epg: &mut Epg,
+    src: &mut R) -> Result<()>
+{
+    let mut de = serde_json::Deserializer::from_reader(src);
+    let mut seed = GuideSeed {
+        epg,
+        error: None,
+    };
+
+    let result = (&mut seed).deserialize(&mut de).and_then(|_| de.end());
+    match seed.error.take() {
+        Some(e) => Err(e),
+        None => Ok(result?),
+    }
+}
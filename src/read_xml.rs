@@ -1,6 +1,9 @@
 use std::{
     io,
-    collections::HashMap,
+    collections::{
+        HashMap,
+        HashSet,
+    },
 };
 
 use chrono::{
@@ -21,6 +24,15 @@ use xml::{
 
 use mpegts::textcode;
 
+#[cfg(feature = "tz")]
+use chrono::NaiveDateTime;
+
+#[cfg(feature = "tz")]
+use crate::{
+    local_time::resolve,
+    LocalTimeDiagnostic,
+};
+
 use crate::{
     Epg,
     EpgChannel,
@@ -35,28 +47,141 @@ pub enum XmlReaderError {
     XmlReader(reader::Error),
     #[error_from("IO: {}", 0)]
     Io(io::Error),
+    #[error_kind("XmlReader: unknown root element: {}", 0)]
+    UnknownRoot(String),
+    #[error_kind("XmlReader: duplicate channel: {}", 0)]
+    DuplicateChannel(String),
+    #[error_kind("XmlReader: undeclared channel: {}", 0)]
+    UndeclaredChannel(String),
+    #[error_kind("XmlReader: {} without attribute {}", 0, 1)]
+    MissingAttribute(&'static str, &'static str),
+    #[error_kind("XmlReader: invalid date: {}", 0)]
+    InvalidDate(String),
 }
 
 
 type Result<T> = std::result::Result<T, XmlReaderError>;
 
 
-fn parse_date(value: &str) -> u64 {
+fn parse_date(value: &str) -> Option<i64> {
     if value.len() > 14 {
         match DateTime::parse_from_str(value, FMT_DATETIME) {
-            Ok(v) => v.timestamp() as u64,
-            _ => 0,
+            Ok(v) => Some(v.timestamp()),
+            _ => None,
         }
     } else if (value.len() == 14) || (value.len() == 12) {
         /* 14: %Y%m%d%H%M%S */
         /* 12: %Y%m%d%H%M */
         let x = value.len() - 2;
         match Utc.datetime_from_str(value, &FMT_DATETIME[.. x]) {
-            Ok(v) => v.timestamp() as u64,
-            _ => 0,
+            Ok(v) => Some(v.timestamp()),
+            _ => None,
         }
     } else {
-        0
+        None
+    }
+}
+
+
+/// Reader state for one XMLTV document
+struct Context<'a> {
+    epg: &'a mut Epg,
+    strict: bool,
+    keep_undeclared: bool,
+    /// Channels declared in the current document
+    declared: HashSet<String>,
+    /// Events for channels not declared yet
+    pending: HashMap<String, Vec<EpgEvent>>,
+}
+
+
+impl<'a> Context<'a> {
+    /// Parses date attribute. In the lenient mode invalid date is None
+    #[cfg_attr(not(feature = "tz"), allow(unused_variables))]
+    fn date(&mut self, channel: &str, value: &str) -> Result<Option<i64>> {
+        #[cfg(feature = "tz")]
+        let result = match self.epg.options.timezone(channel) {
+            Some(tz) if value.len() == 14 || value.len() == 12 => self.local_date(tz, channel, value),
+            _ => parse_date(value),
+        };
+
+        #[cfg(not(feature = "tz"))]
+        let result = parse_date(value);
+
+        match result {
+            Some(v) => Ok(Some(v)),
+            None if self.strict => Err(XmlReaderError::InvalidDate(value.to_owned())),
+            None => Ok(None),
+        }
+    }
+
+    /// Parses date without offset in the time zone.
+    /// Time at daylight saving time transition reported in diagnostics
+    #[cfg(feature = "tz")]
+    fn local_date(&mut self, tz: chrono_tz::Tz, channel: &str, value: &str) -> Option<i64> {
+        let x = value.len() - 2;
+        let local = NaiveDateTime::parse_from_str(value, &FMT_DATETIME[.. x]).ok()?;
+
+        let (time, issue) = resolve(tz, &local, self.epg.options.dst_policy);
+        if let Some(issue) = issue {
+            self.epg.diagnostics.push(LocalTimeDiagnostic {
+                channel: channel.to_owned(),
+                value: value.to_owned(),
+                timezone: tz,
+                issue,
+                resolved: time,
+            });
+        }
+
+        time
+    }
+
+    /// Appends event to the channel. In the strict mode channel
+    /// should be declared in the current document, events for channel
+    /// loaded before are buffered till its declaration
+    fn push_event(&mut self, id: String, event: EpgEvent) {
+        let declared = ! self.strict || self.declared.contains(&id);
+        match self.epg.channels.get_mut(&id) {
+            Some(channel) if declared => channel.events.push(event),
+            _ => self.pending.entry(id).or_default().push(event),
+        }
+    }
+
+    /// Appends events buffered for channels declared after its programmes
+    /// and sorts all channels
+    fn finish(&mut self) -> Result<()> {
+        for (id, events) in self.pending.drain() {
+            let declared = if self.strict {
+                self.declared.contains(&id)
+            } else {
+                self.epg.channels.contains_key(&id)
+            };
+
+            if ! declared {
+                if self.strict {
+                    return Err(XmlReaderError::UndeclaredChannel(id));
+                }
+                if ! self.keep_undeclared {
+                    continue;
+                }
+            }
+
+            let channel = self.epg.channels
+                .entry(id)
+                .or_default();
+
+            for event in events {
+                if channel.last_event_start < event.start {
+                    channel.events.push(event);
+                }
+            }
+        }
+
+        for channel in self.epg.channels.values_mut() {
+            channel.sort();
+        }
+
+        Ok(())
     }
 }
 
@@ -98,7 +223,7 @@ fn parse_xml_value<R: io::Read>(
 
     let value = map
         .entry(lang)
-        .or_insert_with(String::new);
+        .or_default();
 
     while let Some(e) = reader.next() {
         match e? {
@@ -114,7 +239,7 @@ fn parse_xml_value<R: io::Read>(
 
 
 fn read_xml_channel<R: io::Read>(
-    epg: &mut Epg,
+    ctx: &mut Context,
     reader: &mut Events<R>,
     attrs: &[OwnedAttribute]) -> Result<()>
 {
@@ -127,10 +252,22 @@ fn read_xml_channel<R: io::Read>(
     }
 
     if id.is_empty() {
+        if ctx.strict {
+            return Err(XmlReaderError::MissingAttribute("channel", "id"));
+        }
         return skip_xml_element(reader);
     }
 
-    if epg.channels.contains_key(&id) {
+    if ! ctx.declared.insert(id.clone()) && ctx.strict {
+        return Err(XmlReaderError::DuplicateChannel(id));
+    }
+
+    let id = match ctx.epg.options.channel_id(id) {
+        Some(v) => v,
+        None => return skip_xml_element(reader),
+    };
+
+    if ctx.epg.channels.contains_key(&id) {
         return skip_xml_element(reader);
     }
 
@@ -142,7 +279,7 @@ fn read_xml_channel<R: io::Read>(
                 _ => skip_xml_element(reader)?,
             },
             XmlEvent::EndElement { .. } => {
-                epg.channels.insert(id, channel);
+                ctx.epg.channels.insert(id, channel);
                 return Ok(());
             },
             _ => {},
@@ -154,34 +291,55 @@ fn read_xml_channel<R: io::Read>(
 
 
 fn read_xml_programme<R: io::Read>(
-    epg: &mut Epg,
+    ctx: &mut Context,
     reader: &mut Events<R>,
     attrs: &[OwnedAttribute]) -> Result<()>
 {
     let mut event_id: u16 = 0;
     let mut channel = String::new();
-    let mut start: u64 = 0;
-    let mut stop: u64 = 0;
+    let mut start: Option<&str> = None;
+    let mut stop: Option<&str> = None;
 
     for attr in attrs.iter() {
         match attr.name.local_name.as_str() {
             "event_id" => event_id = attr.value.parse::<u16>().unwrap_or(0),
             "channel" => channel.push_str(&attr.value),
-            "start" => start = parse_date(&attr.value),
-            "stop" => stop = parse_date(&attr.value),
+            "start" => start = Some(&attr.value),
+            "stop" => stop = Some(&attr.value),
             _ => {},
         };
     }
 
-    let channel = match epg.channels.get_mut(&channel) {
+    if ctx.strict && channel.is_empty() {
+        return Err(XmlReaderError::MissingAttribute("programme", "channel"));
+    }
+
+    /* denied or not allowed channel skipped before dates parsed */
+    let channel = match ctx.epg.options.channel_id(channel) {
         Some(v) => v,
         None => return skip_xml_element(reader),
     };
 
-    if channel.last_event_start >= start {
-        return skip_xml_element(reader);
+    if ctx.strict && start.is_none() {
+        return Err(XmlReaderError::MissingAttribute("programme", "start"));
     }
 
+    /* time zone defined by id after mapping */
+    let start = match start {
+        Some(v) => Some(ctx.date(&channel, v)?),
+        None => None,
+    };
+    let stop = match stop {
+        Some(v) => ctx.date(&channel, v)?,
+        None => None,
+    };
+
+    /* event without valid start time skipped in the lenient mode */
+    let start = match start.flatten() {
+        Some(v) => v,
+        None => return skip_xml_element(reader),
+    };
+
     let mut event = EpgEvent {
         event_id,
         start,
@@ -189,6 +347,21 @@ fn read_xml_programme<R: io::Read>(
         ..Default::default()
     };
 
+    if let Some(window) = &ctx.epg.options.window {
+        if ! event.is_overlap(window) {
+            return skip_xml_element(reader);
+        }
+    }
+
+    let last_event_start = match ctx.epg.channels.get(&channel) {
+        Some(v) => v.last_event_start,
+        None => i64::MIN,
+    };
+
+    if last_event_start >= start {
+        return skip_xml_element(reader);
+    }
+
     while let Some(e) = reader.next() {
         match e? {
             XmlEvent::StartElement { name, attributes, .. } => match name.local_name.as_str() {
@@ -198,7 +371,7 @@ fn read_xml_programme<R: io::Read>(
                 _ => skip_xml_element(reader)?,
             },
             XmlEvent::EndElement { .. } => {
-                channel.events.push(event);
+                ctx.push_event(channel, event);
                 return Ok(());
             },
             _ => {},
@@ -239,22 +412,35 @@ pub fn read_xml_tv<R: io::BufRead>(
         .create_reader(src)
         .into_iter();
 
+    let mut ctx = Context {
+        strict: epg.options.strict,
+        keep_undeclared: epg.options.keep_undeclared,
+        epg,
+        declared: HashSet::new(),
+        pending: HashMap::new(),
+    };
+
+    let mut root = true;
+
     while let Some(e) = reader.next() {
         match e? {
             XmlEvent::StartElement { name, attributes, .. } => {
-                match name.local_name.as_str() {
+                let name = name.local_name.as_str();
+                if root {
+                    root = false;
+                    if ctx.strict && name != "tv" {
+                        return Err(XmlReaderError::UnknownRoot(name.to_owned()));
+                    }
+                }
+
+                match name {
                     "tv" => {},
-                    "channel" => read_xml_channel(epg, &mut reader, &attributes)?,
-                    "programme" => read_xml_programme(epg, &mut reader, &attributes)?,
+                    "channel" => read_xml_channel(&mut ctx, &mut reader, &attributes)?,
+                    "programme" => read_xml_programme(&mut ctx, &mut reader, &attributes)?,
                     _ => skip_xml_element(&mut reader)?,
                 };
             }
-            XmlEvent::EndDocument => {
-                for channel in epg.channels.values_mut() {
-                    channel.sort();
-                }
-                return Ok(());
-            }
+            XmlEvent::EndDocument => return ctx.finish(),
             _ => {}
         };
     }
@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::{
+    EpgChannel,
+    EpgEvent,
+};
+
+
+/// Serialized `EpgChannel`. `last_event_start` restored from events
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub (crate) struct ChannelData {
+    name: HashMap<String, String>,
+    events: Vec<EpgEvent>,
+}
+
+
+impl From<ChannelData> for EpgChannel {
+    fn from(data: ChannelData) -> Self {
+        EpgChannel {
+            name: data.name,
+            last_event_start: data.events.iter().map(|v| v.start).max().unwrap_or(i64::MIN),
+            events: data.events,
+        }
+    }
+}
+
+
+pub (crate) mod parental_rating {
+    use std::collections::HashMap;
+
+    use serde::{
+        de::Error,
+        Deserialize,
+        Deserializer,
+        Serializer,
+    };
+
+    pub fn serialize<S: Serializer>(map: &HashMap<[u8; 3], u8>, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_map(map.iter().map(|(country, rating)| {
+            (String::from_utf8_lossy(country), rating)
+        }))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<HashMap<[u8; 3], u8>, D::Error> {
+        let map = HashMap::<String, u8>::deserialize(d)?;
+        let mut result = HashMap::with_capacity(map.len());
+
+        for (country, rating) in map {
+            let bytes = country.as_bytes();
+            if bytes.len() != 3 {
+                return Err(D::Error::custom(format!("invalid country code: {}", country)));
+            }
+            result.insert([bytes[0], bytes[1], bytes[2]], rating);
+        }
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,142 @@
+use std::{
+    fs::File,
+    io::{
+        self,
+        BufRead,
+        BufReader,
+    },
+    path::Path,
+    collections::HashMap,
+};
+
+
+#[derive(Debug, Error)]
+pub enum ServiceMapError {
+    #[error_from("ServiceMap IO: {}", 0)]
+    Io(io::Error),
+    #[error_kind("ServiceMap: syntax error at line {}", 0)]
+    Syntax(usize),
+}
+
+
+type Result<T> = std::result::Result<T, ServiceMapError>;
+
+
+/// DVB service identifier
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DvbService {
+    /// Original network id
+    pub onid: u16,
+    /// Transport stream id
+    pub tsid: u16,
+    /// Service id (program number)
+    pub pnr: u16,
+}
+
+
+/// Mapping between XMLTV channel id and DVB service.
+/// One-to-one: new mapping replaces previous for the channel and for the service
+#[derive(Default, Debug, Clone)]
+pub struct ServiceMap {
+    services: HashMap<String, DvbService>,
+    channels: HashMap<DvbService, String>,
+}
+
+
+fn parse_u16(value: &str) -> Option<u16> {
+    match value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) {
+        Some(v) => u16::from_str_radix(v, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+
+impl ServiceMap {
+    /// Maps channel to the service
+    pub fn insert<R: AsRef<str>>(&mut self, id: R, service: DvbService) {
+        let id = id.as_ref();
+        self.remove(id);
+        if let Some(v) = self.channels.remove(&service) {
+            self.services.remove(&v);
+        }
+
+        self.services.insert(id.to_owned(), service);
+        self.channels.insert(service, id.to_owned());
+    }
+
+    /// Removes channel mapping
+    pub fn remove(&mut self, id: &str) -> Option<DvbService> {
+        let service = self.services.remove(id)?;
+        self.channels.remove(&service);
+        Some(service)
+    }
+
+    /// Returns service for the channel
+    #[inline]
+    pub fn service(&self, id: &str) -> Option<&DvbService> {
+        self.services.get(id)
+    }
+
+    /// Returns channel id for the service
+    #[inline]
+    pub fn channel(&self, service: &DvbService) -> Option<&str> {
+        self.channels.get(service).map(String::as_str)
+    }
+
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &DvbService)> {
+        self.services.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.services.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.services.is_empty()
+    }
+
+    /// Reads mapping from the config. Each line is a channel id followed
+    /// by onid, tsid and service id, decimal or hex with `0x` prefix.
+    /// Empty lines and lines started with `#` are ignored:
+    ///
+    /// ```text
+    /// # id      onid  tsid  sid
+    /// bbc1.uk   2     2041  6301
+    /// ```
+    pub fn read<R: BufRead>(&mut self, src: R) -> Result<()> {
+        for (i, line) in src.lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() != 4 {
+                return Err(ServiceMapError::Syntax(i + 1));
+            }
+
+            let mut ids = [0; 3];
+            for (id, value) in ids.iter_mut().zip(&fields[1 ..]) {
+                *id = parse_u16(value).ok_or(ServiceMapError::Syntax(i + 1))?;
+            }
+
+            self.insert(fields[0], DvbService {
+                onid: ids[0],
+                tsid: ids[1],
+                pnr: ids[2],
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Reads mapping from the config file
+    pub fn open<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let file = File::open(path)?;
+        self.read(BufReader::new(file))
+    }
+}
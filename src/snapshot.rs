@@ -0,0 +1,281 @@
+use std::{
+    io::{
+        self,
+        Read,
+        Write,
+    },
+    collections::HashMap,
+};
+
+use crate::{
+    Epg,
+    EpgChannel,
+    EpgEvent,
+};
+
+
+/// Snapshot file header: magic, version, payload size.
+/// Payload followed by CRC-32 of the payload.
+/// Version 2: signed event times.
+/// Version 3: optional stop time
+const MAGIC: &[u8; 4] = b"EPGS";
+pub const SNAPSHOT_VERSION: u16 = 3;
+
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error_from("Snapshot IO: {}", 0)]
+    Io(io::Error),
+    #[error_kind("Snapshot: invalid format")]
+    Format,
+    #[error_kind("Snapshot: incompatible version {}", 0)]
+    Version(u16),
+    #[error_kind("Snapshot: checksum mismatch")]
+    Checksum,
+}
+
+
+type Result<T> = std::result::Result<T, SnapshotError>;
+
+
+struct Encoder {
+    buf: Vec<u8>,
+}
+
+
+impl Encoder {
+    #[inline]
+    fn u8(&mut self, value: u8) { self.buf.push(value) }
+
+    #[inline]
+    fn u16(&mut self, value: u16) { self.buf.extend_from_slice(&value.to_be_bytes()) }
+
+    #[inline]
+    fn u32(&mut self, value: u32) { self.buf.extend_from_slice(&value.to_be_bytes()) }
+
+    #[inline]
+    fn i64(&mut self, value: i64) { self.buf.extend_from_slice(&value.to_be_bytes()) }
+
+    fn str(&mut self, value: &str) {
+        self.u32(value.len() as u32);
+        self.buf.extend_from_slice(value.as_bytes());
+    }
+
+    fn map(&mut self, map: &HashMap<String, String>) {
+        self.u32(map.len() as u32);
+        for (key, value) in map {
+            self.str(key);
+            self.str(value);
+        }
+    }
+
+    fn event(&mut self, event: &EpgEvent) {
+        self.u16(event.event_id);
+        self.i64(event.start);
+        match event.stop {
+            Some(v) => {
+                self.u8(1);
+                self.i64(v);
+            }
+            None => self.u8(0),
+        }
+        self.map(&event.title);
+        self.map(&event.subtitle);
+        self.map(&event.desc);
+        self.u8(event.codepage);
+        self.u32(event.parental_rating.len() as u32);
+        for (country, rating) in &event.parental_rating {
+            self.buf.extend_from_slice(country);
+            self.u8(*rating);
+        }
+    }
+
+    fn channel(&mut self, id: &str, channel: &EpgChannel) {
+        self.str(id);
+        self.map(&channel.name);
+        self.i64(channel.last_event_start);
+        self.u32(channel.events.len() as u32);
+        for event in &channel.events {
+            self.event(event);
+        }
+    }
+}
+
+
+struct Decoder<'a> {
+    buf: &'a [u8],
+}
+
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, size: usize) -> Result<&'a [u8]> {
+        if self.buf.len() < size {
+            return Err(SnapshotError::Format);
+        }
+        let (head, tail) = self.buf.split_at(size);
+        self.buf = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let b = self.take(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        let mut b = [0u8; 8];
+        b.copy_from_slice(self.take(8)?);
+        Ok(u64::from_be_bytes(b))
+    }
+
+    fn i64(&mut self) -> Result<i64> {
+        let mut b = [0u8; 8];
+        b.copy_from_slice(self.take(8)?);
+        Ok(i64::from_be_bytes(b))
+    }
+
+    fn str(&mut self) -> Result<String> {
+        let size = self.u32()? as usize;
+        let b = self.take(size)?;
+        String::from_utf8(b.to_vec()).map_err(|_| SnapshotError::Format)
+    }
+
+    fn map(&mut self) -> Result<HashMap<String, String>> {
+        let count = self.u32()? as usize;
+        let mut map = HashMap::new();
+        for _ in 0 .. count {
+            let key = self.str()?;
+            let value = self.str()?;
+            map.insert(key, value);
+        }
+        Ok(map)
+    }
+
+    fn event(&mut self) -> Result<EpgEvent> {
+        let mut event = EpgEvent {
+            event_id: self.u16()?,
+            start: self.i64()?,
+            stop: match self.u8()? {
+                0 => None,
+                1 => Some(self.i64()?),
+                _ => return Err(SnapshotError::Format),
+            },
+            title: self.map()?,
+            subtitle: self.map()?,
+            desc: self.map()?,
+            codepage: self.u8()?,
+            ..Default::default()
+        };
+
+        let count = self.u32()? as usize;
+        for _ in 0 .. count {
+            let b = self.take(3)?;
+            let country = [b[0], b[1], b[2]];
+            let rating = self.u8()?;
+            event.parental_rating.insert(country, rating);
+        }
+
+        Ok(event)
+    }
+
+    fn channel(&mut self) -> Result<(String, EpgChannel)> {
+        let id = self.str()?;
+        let mut channel = EpgChannel {
+            name: self.map()?,
+            last_event_start: self.i64()?,
+            ..Default::default()
+        };
+
+        let count = self.u32()? as usize;
+        for _ in 0 .. count {
+            channel.events.push(self.event()?);
+        }
+
+        Ok((id, channel))
+    }
+}
+
+
+/// Writes guide snapshot
+pub fn write_snapshot<W: Write>(
+    epg: &Epg,
+    mut dst: W) -> Result<()>
+{
+    let mut encoder = Encoder { buf: Vec::new() };
+    encoder.u32(epg.channels.len() as u32);
+    for (id, channel) in &epg.channels {
+        encoder.channel(id, channel);
+    }
+
+    let payload = encoder.buf;
+
+    dst.write_all(MAGIC)?;
+    dst.write_all(&SNAPSHOT_VERSION.to_be_bytes())?;
+    dst.write_all(&(payload.len() as u64).to_be_bytes())?;
+    dst.write_all(&payload)?;
+    dst.write_all(&crc32fast::hash(&payload).to_be_bytes())?;
+
+    Ok(())
+}
+
+
+/// Reads guide snapshot. Replaces all channels in the guide
+pub fn read_snapshot<R: Read>(
+    epg: &mut Epg,
+    src: &mut R) -> Result<()>
+{
+    let mut header = [0u8; 14];
+    match src.read_exact(&mut header) {
+        Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => return Err(SnapshotError::Format),
+        v => v?,
+    };
+
+    if &header[.. 4] != MAGIC {
+        return Err(SnapshotError::Format);
+    }
+
+    let mut decoder = Decoder { buf: &header[4 ..] };
+    let version = decoder.u16()?;
+    if version != SNAPSHOT_VERSION {
+        return Err(SnapshotError::Version(version));
+    }
+    let size = decoder.u64()?;
+    /* payload size and checksum */
+    let total = size.checked_add(4).ok_or(SnapshotError::Format)?;
+
+    let mut payload = Vec::new();
+    src.take(total).read_to_end(&mut payload)?;
+    if payload.len() as u64 != total {
+        return Err(SnapshotError::Format);
+    }
+
+    let crc = payload.split_off(size as usize);
+    if crc32fast::hash(&payload).to_be_bytes() != crc.as_slice() {
+        return Err(SnapshotError::Checksum);
+    }
+
+    let mut decoder = Decoder { buf: &payload };
+    /* count not trusted for preallocation */
+    let count = decoder.u32()? as usize;
+    let mut channels = HashMap::new();
+    for _ in 0 .. count {
+        let (id, channel) = decoder.channel()?;
+        channels.insert(id, channel);
+    }
+
+    if ! decoder.buf.is_empty() {
+        return Err(SnapshotError::Format);
+    }
+
+    epg.channels = channels;
+    Ok(())
+}
@@ -0,0 +1,175 @@
+use std::{
+    fmt,
+    fs::{
+        self,
+        File,
+    },
+    io::{
+        BufRead,
+        BufReader,
+    },
+    sync::Arc,
+    collections::HashMap,
+};
+
+use crate::{
+    EpgError,
+    LoadPolicy,
+    load_policy::TempFile,
+};
+
+
+/// Source backend for `Epg::load`
+pub trait EpgSource: Send + Sync {
+    /// Opens source. `url` is a full source address including scheme
+    fn open(&self, url: &str) -> Result<Box<dyn BufRead>, EpgError>;
+
+    /// Opens source if it is modified since previous load.
+    /// Returns None if source is not modified.
+    /// By default source is always modified
+    fn open_modified(&self, url: &str) -> Result<Option<Box<dyn BufRead>>, EpgError> {
+        self.open(url).map(Some)
+    }
+}
+
+
+/// Returns true if value is a valid URL scheme:
+/// letter followed by letters, digits, `+`, `-` or `.`.
+/// Single letter is a drive name on Windows and not a scheme
+fn is_scheme(value: &str) -> bool {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() => {},
+        _ => return false,
+    };
+
+    value.len() > 1 && chars.all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+}
+
+
+/// Splits source address into scheme and path: `scheme://path` or
+/// `scheme:path` (for example `data:` URI). Address without scheme is a file path
+pub fn split_url(url: &str) -> (&str, &str) {
+    if let Some(i) = url.find(':') {
+        let scheme = &url[.. i];
+        if is_scheme(scheme) {
+            let path = &url[i + 1 ..];
+            return (scheme, path.strip_prefix("//").unwrap_or(path));
+        }
+    }
+
+    ("file", url)
+}
+
+
+/// Local file: `file:///path/to/file.xml` or just `/path/to/file.xml`
+pub struct FileSource;
+
+
+impl EpgSource for FileSource {
+    fn open(&self, url: &str) -> Result<Box<dyn BufRead>, EpgError> {
+        let (_, path) = split_url(url);
+        let file = File::open(path)?;
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+
+/// Remote file: `http://` or `https://`.
+/// Response downloaded to the temporary file before parsing,
+/// so request repeated on transfer errors
+#[derive(Default, Debug, Clone)]
+pub struct HttpSource {
+    /// Timeouts, retries, redirects and response size limit
+    pub policy: LoadPolicy,
+}
+
+
+impl EpgSource for HttpSource {
+    fn open(&self, url: &str) -> Result<Box<dyn BufRead>, EpgError> {
+        let path = TempFile::path();
+        let client = match self.policy.download(url, &[], &path) {
+            Ok(v) => v,
+            Err(e) => {
+                fs::remove_file(&path).ok();
+                return Err(e);
+            }
+        };
+
+        match client.response.code {
+            200 => Ok(Box::new(TempFile::open(path)?)),
+            v => Err(EpgError::HttpStatus(v)),
+        }
+    }
+}
+
+
+/// Source backends registry. Maps URL scheme to the backend
+#[derive(Clone)]
+pub struct EpgSources {
+    inner: HashMap<String, Arc<dyn EpgSource>>,
+}
+
+
+impl Default for EpgSources {
+    fn default() -> Self {
+        let mut sources = EpgSources {
+            inner: HashMap::new(),
+        };
+
+        sources.register("file", FileSource);
+        sources.register_http(HttpSource::default());
+
+        sources
+    }
+}
+
+
+impl fmt::Debug for EpgSources {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.inner.keys()).finish()
+    }
+}
+
+
+impl EpgSources {
+    /// Registers backend for the scheme. Replaces previous backend if exists
+    pub fn register<S, T>(&mut self, scheme: S, source: T)
+    where
+        S: Into<String>,
+        T: EpgSource + 'static,
+    {
+        self.inner.insert(scheme.into(), Arc::new(source));
+    }
+
+    /// Registers backend for the `http` and `https` schemes
+    pub fn register_http<T: EpgSource + 'static>(&mut self, source: T) {
+        let source: Arc<dyn EpgSource> = Arc::new(source);
+        self.inner.insert("http".to_owned(), source.clone());
+        self.inner.insert("https".to_owned(), source);
+    }
+
+    /// Removes backend for the scheme
+    pub fn unregister(&mut self, scheme: &str) {
+        self.inner.remove(scheme);
+    }
+
+    fn get(&self, url: &str) -> Result<&dyn EpgSource, EpgError> {
+        let (scheme, _) = split_url(url);
+        match self.inner.get(scheme) {
+            Some(source) => Ok(source.as_ref()),
+            None => Err(EpgError::UnknownSourceType),
+        }
+    }
+
+    /// Opens source with backend selected by the URL scheme
+    pub fn open(&self, url: &str) -> Result<Box<dyn BufRead>, EpgError> {
+        self.get(url)?.open(url)
+    }
+
+    /// Opens source if it is modified since previous load.
+    /// Returns None if source is not modified
+    pub fn open_modified(&self, url: &str) -> Result<Option<Box<dyn BufRead>>, EpgError> {
+        self.get(url)?.open_modified(url)
+    }
+}
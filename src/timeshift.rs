@@ -0,0 +1,35 @@
+use crate::EpgChannel;
+
+
+/// Virtual channel derived from the parent channel with time offset
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Timeshift {
+    /// Parent channel id
+    pub parent: String,
+    /// Time offset in seconds
+    pub offset: i64,
+    /// Suffix appended to the channel names
+    pub name_suffix: String,
+}
+
+
+impl Timeshift {
+    pub fn new<S: Into<String>>(parent: S, offset: i64) -> Self {
+        Timeshift {
+            parent: parent.into(),
+            offset,
+            ..Default::default()
+        }
+    }
+
+    /// Builds virtual channel from the parent channel
+    pub fn apply(&self, parent: &EpgChannel) -> EpgChannel {
+        let mut channel = parent.shift(self.offset);
+        if ! self.name_suffix.is_empty() {
+            for name in channel.name.values_mut() {
+                name.push_str(&self.name_suffix);
+            }
+        }
+        channel
+    }
+}
@@ -0,0 +1,101 @@
+use std::{
+    io,
+    collections::HashMap,
+};
+
+use chrono::{
+    TimeZone,
+    Utc,
+};
+
+use serde::Serialize;
+
+use crate::{
+    Epg,
+    serde_util::parental_rating,
+};
+
+
+pub (crate) const FMT_ISO8601: &str = "%Y-%m-%dT%H:%M:%SZ";
+
+
+#[derive(Serialize)]
+struct JsonEvent<'a> {
+    event_id: u16,
+    start: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop: Option<String>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    title: &'a HashMap<String, String>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    subtitle: &'a HashMap<String, String>,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    desc: &'a HashMap<String, String>,
+    #[serde(skip_serializing_if = "HashMap::is_empty", serialize_with = "parental_rating::serialize")]
+    parental_rating: &'a HashMap<[u8; 3], u8>,
+}
+
+
+fn format_date(value: i64) -> io::Result<String> {
+    match Utc.timestamp_opt(value, 0).single() {
+        Some(v) => Ok(v.format(FMT_ISO8601).to_string()),
+        None => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("JsonWriter: date out of range: {}", value))),
+    }
+}
+
+
+/// Writes guide in the JSON format:
+///
+/// ```text
+/// {"channels":[
+/// {"id":"<channel id>","name":{"<lang>":"<name>"},"events":[
+/// {"event_id":1,"start":"2019-01-01T00:00:00Z","stop":"2019-01-01T01:00:00Z","title":{"<lang>":"<text>"},...},
+/// ...
+/// ]},
+/// ...
+/// ]}
+/// ```
+///
+/// Undefined `stop` and empty `title`, `subtitle`, `desc` and `parental_rating` are omitted.
+/// Each event is written directly to the destination
+pub fn write_json<W: io::Write>(
+    epg: &Epg,
+    mut dst: W) -> io::Result<()>
+{
+    dst.write_all(b"{\"channels\":[\n")?;
+
+    for (n, (id, channel)) in epg.channels.iter().enumerate() {
+        if n != 0 {
+            dst.write_all(b",\n")?;
+        }
+
+        dst.write_all(b"{\"id\":")?;
+        serde_json::to_writer(&mut dst, id)?;
+        dst.write_all(b",\"name\":")?;
+        serde_json::to_writer(&mut dst, &channel.name)?;
+        dst.write_all(b",\"events\":[")?;
+
+        for (n, event) in channel.events.iter().enumerate() {
+            dst.write_all(if n == 0 { b"\n" } else { b",\n" })?;
+            serde_json::to_writer(&mut dst, &JsonEvent {
+                event_id: event.event_id,
+                start: format_date(event.start)?,
+                stop: match event.stop {
+                    Some(v) => Some(format_date(v)?),
+                    None => None,
+                },
+                title: &event.title,
+                subtitle: &event.subtitle,
+                desc: &event.desc,
+                parental_rating: &event.parental_rating,
+            })?;
+        }
+
+        dst.write_all(b"\n]}")?;
+    }
+
+    dst.write_all(b"\n]}\n")?;
+    Ok(())
+}
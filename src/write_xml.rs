@@ -30,12 +30,22 @@ use crate::{
 pub enum XmlWriterError {
     #[error_from("XmlWriter: {}", 0)]
     XmlWriter(writer::Error),
+    #[error_kind("XmlWriter: date out of range: {}", 0)]
+    InvalidDate(i64),
 }
 
 
 type Result<T> = std::result::Result<T, XmlWriterError>;
 
 
+fn format_date(value: i64) -> Result<String> {
+    match Utc.timestamp_opt(value, 0).single() {
+        Some(v) => Ok(v.format(FMT_DATETIME).to_string()),
+        None => Err(XmlWriterError::InvalidDate(value)),
+    }
+}
+
+
 fn write_xml_value<W: io::Write>(
     map: &HashMap<String, String>,
     w: &mut EventWriter<W>,
@@ -79,11 +89,21 @@ fn write_xml_programme<W: io::Write>(
 {
     for (id, channel) in &epg.channels {
         for event in &channel.events {
-            w.write(XmlEvent::start_element("programme")
-                .attr("event_id", &event.event_id.to_string())
+            let event_id = event.event_id.to_string();
+            let start = format_date(event.start)?;
+            let stop = match event.stop {
+                Some(v) => Some(format_date(v)?),
+                None => None,
+            };
+
+            let mut element = XmlEvent::start_element("programme")
+                .attr("event_id", &event_id)
                 .attr("channel", id)
-                .attr("start", &Utc.timestamp(event.start as i64, 0).format(FMT_DATETIME).to_string())
-                .attr("stop", &Utc.timestamp(event.stop as i64, 0).format(FMT_DATETIME).to_string()))?;
+                .attr("start", &start);
+            if let Some(stop) = &stop {
+                element = element.attr("stop", stop);
+            }
+            w.write(element)?;
 
             write_xml_value(&event.title, w, "title")?;
             write_xml_value(&event.subtitle, w, "sub-title")?;
@@ -0,0 +1,56 @@
+extern crate epg;
+
+use epg::*;
+
+#[test]
+fn test_aggregate() {
+    let mut aggregator = EpgAggregator::default();
+
+    let mut options = EpgOptions::default();
+    options.map.insert("prov-1".to_string(), "id-1".to_string());
+    aggregator.add("file://tests/docs/e7.xml", 5, options);
+
+    aggregator.add("file://tests/docs/e3-1.xml", 10, EpgOptions::default());
+    aggregator.add("file://tests/docs/not-found.xml", 1, EpgOptions::default());
+
+    let result = aggregator.load();
+
+    assert_eq!(result.errors.len(), 1);
+    assert_eq!(result.errors[0].0, 2);
+
+    let channel = result.epg.channels.get("id-1").unwrap();
+    assert_eq!(channel.name.get("eng").unwrap(), "Test Channel");
+    assert_eq!(channel.name.get("pol").unwrap(), "Kanał");
+
+    let titles: Vec<&str> = channel.events.iter()
+        .map(|e| e.title.get("eng").unwrap().as_str())
+        .collect();
+    assert_eq!(titles, vec!["Title #1", "Title #2", "Backup #3", "Backup #4"]);
+
+    assert_eq!(result.origin.get("id-1").unwrap(), &vec![1, 1, 0, 0]);
+
+    // source event ids kept, collision resolved with unused id
+    let ids: Vec<u16> = channel.events.iter().map(|e| e.event_id).collect();
+    assert_eq!(ids, vec![0, 1, 3, 2]);
+}
+
+#[test]
+fn test_aggregate_without_stop() {
+    let mut aggregator = EpgAggregator::default();
+
+    // event without stop time lasts till the next event
+    aggregator.add("file://tests/docs/e11.xml", 10, EpgOptions::default());
+
+    let mut options = EpgOptions::default();
+    options.map.insert("prov-1".to_string(), "id-1".to_string());
+    aggregator.add("file://tests/docs/e7.xml", 5, options);
+
+    let result = aggregator.load();
+    assert!(result.errors.is_empty());
+
+    let channel = result.epg.channels.get("id-1").unwrap();
+    let titles: Vec<&str> = channel.events.iter()
+        .map(|e| e.title.get("eng").unwrap().as_str())
+        .collect();
+    assert_eq!(titles, vec!["Title #1", "Title #2"]);
+}
@@ -0,0 +1,46 @@
+extern crate epg;
+
+use epg::*;
+
+#[test]
+fn test_diff() {
+    let mut old = Epg::default();
+    old.load("file://tests/docs/e3-2.xml").unwrap();
+
+    let mut new = Epg::default();
+    new.load("file://tests/docs/e3-2.xml").unwrap();
+    assert!(EpgDiff::new(&old, &new, DiffMatch::Start).is_empty());
+
+    // e3-2.xml: 06:00, 07:00, 07:30, 08:00 .. 08:30 (+0300) 2018-08-20
+    let t0700 = 1534737600;
+    let t0900 = 1534748400;
+
+    let channel = new.channels.get_mut("id-1").unwrap();
+    channel.events[1].title.insert("eng".to_owned(), "Changed".to_owned());
+    channel.events[1].stop = channel.events[1].stop.map(|v| v + 60);
+    let removed = channel.events.remove(2);
+    channel.events.push(EpgEvent {
+        start: t0900,
+        stop: Some(t0900 + 1800),
+        ..Default::default()
+    });
+
+    let diff = EpgDiff::new(&old, &new, DiffMatch::Start);
+    assert_eq!(diff.channels.len(), 1);
+
+    let channel = diff.channels.get("id-1").unwrap();
+    assert_eq!(channel.added.len(), 1);
+    assert_eq!(channel.added[0].start, t0900);
+    assert_eq!(channel.removed, vec![&removed]);
+    assert_eq!(channel.modified.len(), 1);
+    assert_eq!(channel.modified[0].new.start, t0700);
+    assert_eq!(channel.modified[0].fields, vec![EventField::Stop, EventField::Title]);
+    assert_eq!(channel.range(), Some(t0700 .. t0900 + 1800));
+
+    // removed channel
+    new.channels.remove("id-1");
+    let diff = EpgDiff::new(&old, &new, DiffMatch::EventId);
+    let channel = diff.channels.get("id-1").unwrap();
+    assert!(channel.added.is_empty());
+    assert_eq!(channel.removed.len(), old.channels.get("id-1").unwrap().events.len());
+}
@@ -0,0 +1,179 @@
+extern crate epg;
+extern crate mpegts;
+
+use std::convert::TryFrom;
+
+use mpegts::psi::{
+    Eit,
+    EitItem,
+    Psi,
+};
+
+use epg::*;
+
+#[test]
+fn test_dvb_time() {
+    // ETSI EN 300 468 Annex C: 1993-10-13 12:45:00
+    let time = 750516300;
+    let data = [0xC0, 0x79, 0x12, 0x45, 0x00];
+    assert_eq!(encode_time(time).unwrap(), data);
+    assert_eq!(decode_time(&data).unwrap(), time);
+    assert_eq!(time_to_mjd(time).unwrap(), 0xC079);
+
+    // range limits
+    assert_eq!(DVB_TIME_MIN, -2203891200);
+    assert_eq!(DVB_TIME_MAX, 2155593599);
+    assert_eq!(encode_time(DVB_TIME_MIN).unwrap(), [0x3A, 0xE7, 0x00, 0x00, 0x00]);
+    assert_eq!(decode_time(&encode_time(-1).unwrap()).unwrap(), -1);
+    assert_eq!(decode_time(&encode_time(DVB_TIME_MAX).unwrap()).unwrap(), DVB_TIME_MAX);
+
+    assert!(matches!(encode_time(DVB_TIME_MIN - 1), Err(DvbTimeError::TimeOutOfRange(_))));
+    assert!(matches!(encode_time(DVB_TIME_MAX + 1), Err(DvbTimeError::TimeOutOfRange(_))));
+    assert!(matches!(
+        decode_time(&[0x00, 0x00, 0x00, 0x00, 0x00]),
+        Err(DvbTimeError::TimeOutOfRange(_))));
+
+    // undefined start time
+    assert!(matches!(decode_time(&[0xFF, 0xFF, 0xFF, 0xFF, 0xFF]), Err(DvbTimeError::InvalidBcd)));
+    assert!(matches!(decode_time(&[0xC0, 0x79, 0x24, 0x00, 0x00]), Err(DvbTimeError::InvalidBcd)));
+
+    // duration
+    assert_eq!(encode_duration(6330).unwrap(), [0x01, 0x45, 0x30]);
+    assert_eq!(decode_duration(&[0x01, 0x45, 0x30]).unwrap(), 6330);
+    assert_eq!(encode_duration(DVB_DURATION_MAX).unwrap(), [0x99, 0x59, 0x59]);
+    assert_eq!(decode_duration(&[0x99, 0x59, 0x59]).unwrap(), DVB_DURATION_MAX);
+
+    assert!(matches!(
+        encode_duration(DVB_DURATION_MAX + 1),
+        Err(DvbTimeError::DurationOutOfRange(_))));
+    assert!(matches!(decode_duration(&[0x00, 0x60, 0x00]), Err(DvbTimeError::InvalidBcd)));
+}
+
+#[test]
+fn test_split_events() {
+    let mut channel = EpgChannel::default();
+    channel.events.push(EpgEvent {
+        event_id: 1,
+        start: 1534734000,
+        stop: Some(1534734000 + EIT_MAX_DURATION + 60),
+        ..Default::default()
+    });
+    channel.events.push(EpgEvent {
+        start: 1534734000 + EIT_MAX_DURATION + 60,
+        stop: Some(1534734000 + EIT_MAX_DURATION + 3660),
+        ..Default::default()
+    });
+    channel.sort();
+
+    channel.split_events(EIT_MAX_DURATION);
+    assert_eq!(channel.events.len(), 3);
+    assert_eq!(channel.events[0].stop, Some(channel.events[0].start + EIT_MAX_DURATION));
+    assert_eq!(channel.events[0].stop, Some(channel.events[1].start));
+    assert_eq!(channel.events[1].stop, Some(channel.events[1].start + 60));
+
+    // next part with unused id, other events not renumbered
+    assert_eq!(channel.events[0].event_id, 1);
+    assert_eq!(channel.events[1].event_id, 3);
+    assert_eq!(channel.events[2].event_id, 2);
+
+    let mut epg = Epg::default();
+    epg.services.insert("id-1", DvbService { onid: 1, tsid: 1, pnr: 100 });
+    epg.channels.insert("id-1".to_owned(), channel);
+
+    let mut generator = EitGenerator::default();
+    let schedule = generator.schedule(&epg, "id-1", 1534734000);
+    let items: usize = schedule.iter().map(|v| v.eit.items.len()).sum();
+    assert_eq!(items, 3);
+    assert!(generator.errors.is_empty());
+}
+
+#[test]
+fn test_parse_undefined_time() {
+    let mut eit = Eit {
+        table_id: 0x50,
+        pnr: 100,
+        tsid: 1,
+        onid: 1,
+        ..Default::default()
+    };
+    eit.items.push(EitItem {
+        event_id: 1,
+        start: 1534734000,
+        duration: 3600,
+        ..Default::default()
+    });
+    // start out of MJD range encoded as undefined
+    eit.items.push(EitItem {
+        event_id: 2,
+        start: (DVB_TIME_MAX + 1) as u64,
+        duration: 3600,
+        ..Default::default()
+    });
+    // duration out of BCD range encoded as undefined
+    eit.items.push(EitItem {
+        event_id: 3,
+        start: 1534737600,
+        duration: (DVB_DURATION_MAX + 1) as u32,
+        ..Default::default()
+    });
+    // zero duration is defined
+    eit.items.push(EitItem {
+        event_id: 4,
+        start: 1534741200,
+        duration: 0,
+        ..Default::default()
+    });
+
+    let section = EitSection {
+        eit,
+        section_number: 0,
+        last_section_number: 0,
+        segment_last_section_number: 0,
+        last_table_id: 0x50,
+    };
+
+    let mut ts = Vec::new();
+    section.demux(0x12, &mut 0, &mut ts).unwrap();
+
+    let mut psi = Psi::default();
+    for packet in ts.chunks(188) {
+        psi.mux(packet);
+    }
+    assert!(psi.check());
+
+    let data = &psi.buffer;
+    assert_eq!(&data[14 + 2 .. 14 + 7], &encode_time(1534734000).unwrap());
+    assert_eq!(&data[14 + 7 .. 14 + 10], &encode_duration(3600).unwrap());
+    assert_eq!(&data[26 + 2 .. 26 + 7], &[0xFF; 5]);
+    assert_eq!(&data[38 + 7 .. 38 + 10], &[0xFF; 3]);
+
+    let section = EitSection::parse(&psi).unwrap();
+    assert_eq!(section.eit.items[0].start, 1534734000);
+    assert_eq!(section.eit.items[1].start, u64::MAX);
+    assert_eq!(section.eit.items[2].duration, u32::MAX);
+
+    assert_eq!(section.eit.items[3].duration, 0);
+
+    // undefined duration imported without stop time
+    let mut channel = EpgChannel::default();
+    let errors = channel.parse(&section.eit);
+    assert_eq!(channel.events.len(), 3);
+    assert_eq!(channel.events[0].start, 1534734000);
+    assert_eq!(channel.events[0].stop, Some(1534734000 + 3600));
+    assert_eq!(channel.events[1].stop, None);
+    assert_eq!(channel.events[2].stop, Some(1534741200));
+
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(errors[0], (2, EitError::StartOutOfRange(_))));
+
+    // duration out of BCD range not decoded from section
+    let item = EitItem { start: 1534737600, duration: (DVB_DURATION_MAX + 1) as u32, ..Default::default() };
+    assert!(matches!(EpgEvent::try_from(&item), Err(EitError::DurationOverflow(_))));
+
+    // import reports skipped items with channel id
+    let mut epg = Epg::default();
+    epg.services.insert("id-1", DvbService { onid: 1, tsid: 1, pnr: 100 });
+    assert_eq!(epg.parse_eit(&section.eit), Some("id-1"));
+    assert_eq!(epg.eit_errors.len(), 1);
+    assert_eq!(epg.eit_errors[0].0, "id-1");
+}
@@ -0,0 +1,485 @@
+extern crate epg;
+extern crate mpegts;
+
+use std::convert::TryFrom;
+
+use mpegts::psi::{
+    EitItem,
+    Psi,
+};
+
+use epg::*;
+
+/// Total number of events in sections
+fn items(sections: &[EitSection]) -> usize {
+    sections.iter().map(|v| v.eit.items.len()).sum()
+}
+
+#[test]
+fn test_eit_version() {
+    let mut epg = Epg::default();
+    epg.load("file://tests/docs/e3-2.xml").unwrap();
+
+    // e3-2.xml: 06:00, 07:00, 07:30, 08:00 .. 08:30 (+0300) 2018-08-20
+    let t0600 = 1534734000;
+    let t0700 = 1534737600;
+
+    let service = DvbService { onid: 1, tsid: 1, pnr: 100 };
+    epg.services.insert("id-1", service);
+    let mut generator = EitGenerator::default();
+
+    // unchanged content keeps version
+    let pf = generator.present_following(&epg, "id-1", t0600).unwrap();
+    assert_eq!(pf[0].eit.version, 0);
+    let pf = generator.present_following(&epg, "id-1", t0600 + 60).unwrap();
+    assert_eq!(pf[0].eit.version, 0);
+
+    // next event on air
+    let pf = generator.present_following(&epg, "id-1", t0700).unwrap();
+    assert_eq!(pf[0].eit.version, 1);
+    assert_eq!(pf[1].eit.version, 1);
+
+    let schedule = generator.schedule(&epg, "id-1", t0600);
+    assert!(schedule.iter().all(|v| v.eit.version == 0));
+
+    // changed event
+    let channel = epg.channels.get_mut("id-1").unwrap();
+    channel.events[3].title.insert("eng".to_owned(), "Changed".to_owned());
+
+    let pf = generator.present_following(&epg, "id-1", t0700).unwrap();
+    assert_eq!(pf[0].eit.version, 1);
+    let schedule = generator.schedule(&epg, "id-1", t0600);
+    for section in &schedule {
+        let version = if section.eit.table_id == EIT_SCHEDULE_ACTUAL { 1 } else { 0 };
+        assert_eq!(section.eit.version, version);
+    }
+    assert_eq!(generator.version(&service, EIT_SCHEDULE_ACTUAL), Some(1));
+
+    // version wraps at 32
+    for i in 0 .. 31 {
+        let channel = epg.channels.get_mut("id-1").unwrap();
+        channel.events[0].title.insert("eng".to_owned(), i.to_string());
+        generator.schedule(&epg, "id-1", t0600);
+    }
+    assert_eq!(generator.version(&service, EIT_SCHEDULE_ACTUAL), Some(0));
+
+    generator.remove(&service);
+    assert_eq!(generator.version(&service, EIT_SCHEDULE_ACTUAL), None);
+
+    // not mapped channel
+    assert!(generator.present_following(&epg, "id-2", t0600).is_none());
+    assert!(generator.schedule(&epg, "id-2", t0600).is_empty());
+}
+
+#[test]
+fn test_eit_present_following() {
+    let t = 1534734000;
+
+    let mut epg = Epg::default();
+    epg.services.insert("id-1", DvbService { onid: 1, tsid: 1, pnr: 100 });
+    let mut channel = EpgChannel::default();
+    channel.events.push(EpgEvent { start: t, stop: Some(t + 3600), ..Default::default() });
+    channel.events.push(EpgEvent { start: t + 7200, stop: Some(t + 10800), ..Default::default() });
+    channel.sort();
+    epg.channels.insert("id-1".to_owned(), channel);
+
+    let mut generator = EitGenerator::default();
+
+    let pf = generator.present_following(&epg, "id-1", t).unwrap();
+    assert_eq!(pf.len(), 2);
+    for (i, section) in pf.iter().enumerate() {
+        assert_eq!(section.eit.table_id, EIT_PF_ACTUAL);
+        assert_eq!(section.section_number, i as u8);
+        assert_eq!(section.last_section_number, 1);
+        assert_eq!(section.segment_last_section_number, 1);
+        assert_eq!(section.last_table_id, EIT_PF_ACTUAL);
+    }
+
+    // running status at the given time
+    assert_eq!(pf[0].eit.items[0].start, t as u64);
+    assert_eq!(pf[0].eit.items[0].status, 4);
+    assert_eq!(pf[1].eit.items[0].start, t as u64 + 7200);
+    assert_eq!(pf[1].eit.items[0].status, 1);
+
+    // gap between events: nothing on air
+    let pf = generator.present_following(&epg, "id-1", t + 5000).unwrap();
+    assert_eq!(pf.len(), 2);
+    assert!(pf[0].eit.items.is_empty());
+    assert_eq!(pf[1].section_number, 1);
+    assert_eq!(pf[1].eit.items[0].start, t as u64 + 7200);
+
+    // section header in the output
+    let mut cc = 0;
+    let mut ts = Vec::new();
+    pf[1].demux(EIT_PID, &mut cc, &mut ts).unwrap();
+    assert_eq!(ts.len(), 188);
+    assert_eq!(cc, 1);
+    assert_eq!(ts[1] & 0x40, 0x40);
+    assert_eq!(ts[5], EIT_PF_ACTUAL);
+    assert_eq!((ts[11], ts[12]), (1, 1));
+}
+
+#[test]
+fn test_eit_schedule() {
+    let mut epg = Epg::default();
+    epg.load("file://tests/docs/e3-2.xml").unwrap();
+    epg.services.insert("id-1", DvbService { onid: 1, tsid: 1, pnr: 100 });
+
+    // e3-2.xml: 06:00 .. 08:30 (+0300) 2018-08-20. 03:00 .. 05:30 UTC: segment 1
+    let t0600 = 1534734000;
+
+    let mut generator = EitGenerator::default();
+    let schedule = generator.schedule(&epg, "id-1", t0600);
+
+    // sections ordered by table_id and section_number
+    let tables: Vec<u8> = schedule.iter().map(|v| v.eit.table_id).collect();
+    assert_eq!(tables.first(), Some(&EIT_SCHEDULE_ACTUAL));
+    assert_eq!(tables.last(), Some(&(EIT_SCHEDULE_ACTUAL + 1)));
+    for pair in schedule.windows(2) {
+        let a = (pair[0].eit.table_id, pair[0].section_number);
+        let b = (pair[1].eit.table_id, pair[1].section_number);
+        assert!(a < b);
+    }
+
+    for section in &schedule {
+        assert_eq!((section.eit.onid, section.eit.tsid, section.eit.pnr), (1, 1, 100));
+        assert_eq!(section.last_table_id, EIT_SCHEDULE_ACTUAL + 1);
+        assert_eq!(section.last_section_number, 31 * 8);
+        assert!(section.section_number <= section.segment_last_section_number);
+        assert_eq!(section.section_number / 8, section.segment_last_section_number / 8);
+    }
+
+    // 4 events in segment 1 by 2 events per section
+    let segment: Vec<&EitSection> = schedule.iter()
+        .filter(|v| v.eit.table_id == EIT_SCHEDULE_ACTUAL && v.section_number / 8 == 1)
+        .collect();
+    assert_eq!(segment.len(), 2);
+    assert_eq!(segment[0].section_number, 8);
+    assert_eq!(segment[1].section_number, 9);
+    assert_eq!(segment[0].segment_last_section_number, 9);
+    assert_eq!(items(&schedule), epg.channels.get("id-1").unwrap().events.len());
+
+    // event started before midnight and still on air is in the first segment
+    let midnight = 1534723200;
+    let channel = epg.channels.get_mut("id-1").unwrap();
+    channel.events.push(EpgEvent { start: midnight - 3600, stop: Some(midnight + 3600), ..Default::default() });
+    channel.sort();
+
+    let schedule = generator.schedule(&epg, "id-1", midnight + 1800);
+    assert_eq!(schedule[0].section_number, 0);
+    assert_eq!(schedule[0].eit.items.len(), 1);
+    assert_eq!(schedule[0].eit.items[0].start, (midnight - 3600) as u64);
+
+    // ended event not included
+    let schedule = generator.schedule(&epg, "id-1", midnight + 3600);
+    assert!(schedule[0].eit.items.is_empty());
+}
+
+/// Splits TS to sections: table_id, section_number and packets
+fn ts_sections(ts: &[u8]) -> Vec<(u8, u8, Vec<&[u8]>)> {
+    let mut result: Vec<(u8, u8, Vec<&[u8]>)> = Vec::new();
+    for packet in ts.chunks(188) {
+        if packet[1] & 0x40 != 0 {
+            result.push((packet[5], packet[11], vec![packet]));
+        } else {
+            result.last_mut().unwrap().2.push(packet);
+        }
+    }
+    result
+}
+
+/// Checks PID and continuity counter sequence
+fn check_packets(ts: &[u8]) {
+    assert_eq!(ts.len() % 188, 0);
+    let mut cc = ts[3] & 0x0F;
+    for packet in ts.chunks(188) {
+        assert_eq!(packet[0], 0x47);
+        assert_eq!((u16::from(packet[1] & 0x1F) << 8) | u16::from(packet[2]), EIT_PID);
+        assert_eq!(packet[3] & 0x0F, cc);
+        cc = (cc + 1) & 0x0F;
+    }
+}
+
+#[test]
+fn test_eit_carousel() {
+    let mut epg = Epg::default();
+    epg.load("file://tests/docs/e3-2.xml").unwrap();
+
+    epg.services.insert("id-1", DvbService { onid: 1, tsid: 1, pnr: 100 });
+
+    // e3-2.xml: 06:00 (+0300) 2018-08-20
+    let t0600 = 1534734000 * 1000;
+
+    let mut carousel = EitCarousel::new(188 * 8 * 100);
+    let mut ts = Vec::new();
+    carousel.poll(&epg, t0600, &mut ts);
+    assert!(ts.is_empty());
+    assert!(carousel.pending() > 0);
+
+    // 100 ms: 10 packets
+    carousel.poll(&epg, t0600 + 100, &mut ts);
+    assert!(! ts.is_empty());
+    assert!(ts.len() <= 188 * 10);
+    assert_eq!(ts.len() % 188, 0);
+
+    // one second in 100 ms steps
+    for i in 2 .. 12 {
+        carousel.poll(&epg, t0600 + i * 100, &mut ts);
+    }
+    assert!(ts.len() <= 188 * 110);
+    check_packets(&ts);
+
+    // present/following first, then schedule sections in order
+    let sections = ts_sections(&ts);
+    assert_eq!((sections[0].0, sections[0].1), (EIT_PF_ACTUAL, 0));
+    assert_eq!((sections[1].0, sections[1].1), (EIT_PF_ACTUAL, 1));
+    for pair in sections[2 ..].windows(2) {
+        assert!(pair[0].0 >= EIT_SCHEDULE_ACTUAL);
+        assert!((pair[0].0, pair[0].1) < (pair[1].0, pair[1].1));
+    }
+}
+
+#[test]
+fn test_eit_carousel_sections() {
+    let t = 1534734000;
+
+    let mut epg = Epg::default();
+    epg.services.insert("id-1", DvbService { onid: 1, tsid: 1, pnr: 100 });
+    epg.services.insert("id-2", DvbService { onid: 1, tsid: 2, pnr: 200 });
+
+    // sections longer than one packet
+    for id in &["id-1", "id-2"] {
+        let mut channel = EpgChannel::default();
+        for i in 0 .. 4 {
+            let mut event = EpgEvent { start: t + i * 3600, stop: Some(t + (i + 1) * 3600), ..Default::default() };
+            event.title.insert("eng".to_owned(), format!("Title #{}", i));
+            event.desc.insert("eng".to_owned(), "Description ".repeat(30));
+            channel.events.push(event);
+        }
+        channel.sort();
+        epg.channels.insert((*id).to_owned(), channel);
+    }
+
+    // tables rebuilt before previous ones sent
+    let mut carousel = EitCarousel::new(188 * 8 * 100);
+    carousel.pf_interval = 250;
+    carousel.schedule_interval = 700;
+    carousel.schedule_far_interval = 1100;
+    carousel.generator.actual = Some((1, 1));
+
+    let mut ts = Vec::new();
+    for i in 0 .. 100 {
+        carousel.poll(&epg, t * 1000 + i * 100, &mut ts);
+    }
+    check_packets(&ts);
+
+    // each section sent complete
+    let sections = ts_sections(&ts);
+    assert!(sections.iter().any(|v| v.2.len() > 1));
+    for (_, _, packets) in &sections[.. sections.len() - 1] {
+        let mut psi = Psi::default();
+        for packet in packets {
+            psi.mux(packet);
+        }
+        assert!(psi.check());
+    }
+
+    // other transport stream
+    let tables: Vec<u8> = sections.iter().map(|v| v.0).collect();
+    assert!(tables.contains(&EIT_PF_ACTUAL));
+    assert!(tables.contains(&EIT_PF_OTHER));
+    assert!(tables.contains(&EIT_SCHEDULE_ACTUAL));
+    assert!(tables.contains(&EIT_SCHEDULE_OTHER));
+    assert!(! tables.iter().any(|v| *v > EIT_SCHEDULE_ACTUAL + 1 && *v < EIT_SCHEDULE_OTHER));
+}
+
+#[test]
+fn test_eit_carousel_intervals() {
+    // 2018-08-20 00:00 UTC
+    let t = 1534723200;
+
+    let mut epg = Epg::default();
+    epg.services.insert("id-1", DvbService { onid: 1, tsid: 1, pnr: 100 });
+    epg.services.insert("id-2", DvbService { onid: 1, tsid: 2, pnr: 200 });
+
+    // events on the first day and on the third day
+    for id in &["id-1", "id-2"] {
+        let mut channel = EpgChannel::default();
+        for start in &[t + 3600, t + 2 * 86400 + 3600] {
+            channel.events.push(EpgEvent { start: *start, stop: Some(start + 3600), ..Default::default() });
+        }
+        channel.sort();
+        epg.channels.insert((*id).to_owned(), channel);
+    }
+
+    let mut carousel = EitCarousel::new(188 * 8 * 1000);
+    carousel.pf_interval = 500;
+    carousel.pf_other_interval = 1_000;
+    carousel.schedule_interval = 1_000;
+    carousel.schedule_far_interval = 10_000;
+    carousel.schedule_other_interval = 2_000;
+    carousel.schedule_other_far_interval = 10_000;
+    carousel.generator.actual = Some((1, 1));
+
+    let mut ts = Vec::new();
+    for i in 0 .. 40 {
+        carousel.poll(&epg, t * 1000 + i * 100, &mut ts);
+    }
+    check_packets(&ts);
+
+    let sections = ts_sections(&ts);
+    let count = |table_id: u8, range: std::ops::Range<u8>| sections.iter()
+        .filter(|v| v.0 == table_id && range.contains(&v.1))
+        .count();
+
+    // schedule day by the segment: 8 segments of 8 sections in the first day
+    assert_eq!(count(EIT_PF_ACTUAL, 0 .. 2), 8 * 2);
+    assert_eq!(count(EIT_PF_OTHER, 0 .. 2), 4 * 2);
+    assert_eq!(count(EIT_SCHEDULE_ACTUAL, 0 .. 64), 4 * 8);
+    assert_eq!(count(EIT_SCHEDULE_OTHER, 0 .. 64), 2 * 8);
+    assert_eq!(count(EIT_SCHEDULE_ACTUAL, 64 .. 255), 24);
+    assert_eq!(count(EIT_SCHEDULE_OTHER, 64 .. 255), 24);
+}
+
+#[test]
+fn test_service_map() {
+    let mut services = ServiceMap::default();
+    services.open("tests/docs/services.conf").unwrap();
+    assert_eq!(services.len(), 2);
+
+    let service = DvbService { onid: 2, tsid: 2041, pnr: 6301 };
+    assert_eq!(services.service("bbc1.uk"), Some(&service));
+    assert_eq!(services.channel(&service), Some("bbc1.uk"));
+    assert_eq!(services.service("id-1"), Some(&DvbService { onid: 1, tsid: 1, pnr: 100 }));
+
+    // service moved to other channel
+    services.insert("bbc1hd.uk", service);
+    assert_eq!(services.service("bbc1.uk"), None);
+    assert_eq!(services.channel(&service), Some("bbc1hd.uk"));
+
+    assert!(matches!(
+        services.read("bbc2.uk 2 2041\n".as_bytes()),
+        Err(ServiceMapError::Syntax(1))));
+
+    // import: EIT service mapped to channel id
+    let mut src = Epg::default();
+    src.load("file://tests/docs/e3-2.xml").unwrap();
+    src.services = services.clone();
+
+    let mut generator = EitGenerator::default();
+    let schedule = generator.schedule(&src, "id-1", 1534734000);
+
+    let mut epg = Epg {
+        services,
+        ..Default::default()
+    };
+    for section in &schedule {
+        assert_eq!(epg.parse_eit(&section.eit), Some("id-1"));
+    }
+    assert_eq!(epg.channels.get("id-1").unwrap().events.len(), items(&schedule));
+
+    epg.services.remove("id-1");
+    assert_eq!(epg.parse_eit(&schedule[0].eit), None);
+}
+
+#[test]
+fn test_eit_item_range() {
+    let event = |start: i64, stop: Option<i64>| EpgEvent { start, stop, ..Default::default() };
+
+    let item = EitItem::try_from(&event(1534734000, Some(1534734000 + EIT_MAX_DURATION))).unwrap();
+    assert_eq!(item.duration as i64, EIT_MAX_DURATION);
+
+    // event without stop time
+    assert_eq!(EitItem::try_from(&event(1534734000, None)).unwrap().duration, u32::MAX);
+
+    assert!(matches!(
+        EitItem::try_from(&event(-3600, None)),
+        Err(EitError::StartOutOfRange(-3600))));
+    assert!(matches!(
+        EitItem::try_from(&event(1534734000, Some(1534730000))),
+        Err(EitError::StopBeforeStart)));
+    assert!(matches!(
+        EitItem::try_from(&event(1534734000, Some(1534734000 + EIT_MAX_DURATION + 1))),
+        Err(EitError::DurationOverflow(_))));
+
+    // invalid events skipped by generator
+    let mut epg = Epg::default();
+    epg.services.insert("id-1", DvbService { onid: 1, tsid: 1, pnr: 100 });
+    let mut channel = EpgChannel::default();
+    channel.events.push(event(1534734000, Some(1534730000)));
+    channel.events.push(event(1534737600, Some(1534741200)));
+    channel.sort();
+    epg.channels.insert("id-1".to_owned(), channel);
+
+    let mut generator = EitGenerator::default();
+    let schedule = generator.schedule(&epg, "id-1", 1534734000);
+    assert_eq!(items(&schedule), 1);
+    assert_eq!(generator.errors.len(), 1);
+    assert_eq!(generator.errors[0].0, "id-1");
+}
+
+#[test]
+fn test_eit_section_overflow() {
+    let mut eit = mpegts::psi::Eit { table_id: EIT_SCHEDULE_ACTUAL, ..Default::default() };
+    for i in 0 .. 5 {
+        let mut event = EpgEvent {
+            event_id: i,
+            start: 1534734000 + i64::from(i) * 3600,
+            stop: Some(1534734000 + i64::from(i + 1) * 3600),
+            ..Default::default()
+        };
+        event.desc.insert("eng".to_owned(), "x".repeat(1000));
+        eit.items.push(EitItem::try_from(&event).unwrap());
+    }
+
+    let mut section = EitSection {
+        eit,
+        section_number: 0,
+        last_section_number: 0,
+        segment_last_section_number: 0,
+        last_table_id: EIT_SCHEDULE_ACTUAL,
+    };
+
+    // events above 4096 bytes not dropped silently
+    assert!(matches!(section.assemble(), Err(EitError::SectionOverflow)));
+    let mut ts = Vec::new();
+    assert!(section.demux(EIT_PID, &mut 0, &mut ts).is_err());
+    assert!(ts.is_empty());
+
+    section.eit.items.truncate(3);
+    let data = section.assemble().unwrap();
+    assert!(data.len() <= 4096);
+}
+
+#[test]
+fn test_eit_schedule_overflow() {
+    let t = 1534734000;
+    let mut epg = Epg::default();
+    epg.services.insert("id-1", DvbService { onid: 1, tsid: 1, pnr: 100 });
+
+    // 20 events in one segment with 8 sections
+    let mut channel = EpgChannel::default();
+    for i in 0 .. 20 {
+        let mut event = EpgEvent {
+            event_id: i,
+            start: t + i64::from(i) * 60,
+            stop: Some(t + i64::from(i + 1) * 60),
+            ..Default::default()
+        };
+        event.desc.insert("eng".to_owned(), "x".repeat(1000));
+        channel.events.push(event);
+    }
+    channel.sort();
+    epg.channels.insert("id-1".to_owned(), channel);
+
+    let mut generator = EitGenerator::default();
+    let schedule = generator.schedule(&epg, "id-1", t);
+
+    // events not fit into the last section reported
+    assert!(! generator.errors.is_empty());
+    assert!(generator.errors.iter().all(|(id, _, e)| {
+        id == "id-1" && matches!(e, EitError::SectionOverflow)
+    }));
+    assert_eq!(items(&schedule) + generator.errors.len(), 20);
+    assert!(schedule.iter().all(|v| v.assemble().is_ok()));
+}
@@ -0,0 +1,287 @@
+extern crate epg;
+
+use epg::*;
+
+use std::{
+    fs,
+    thread,
+    sync::{
+        mpsc,
+        Arc,
+        atomic::{
+            AtomicUsize,
+            Ordering,
+        },
+    },
+    time::{
+        Duration,
+        Instant,
+    },
+    io::{
+        BufRead,
+        BufReader,
+        Read,
+        Write,
+    },
+    net::TcpListener,
+};
+
+/// HTTP stand-in. Handles requests with the given list of responses:
+/// - status code. 200 serves tests/docs/e2.xml
+/// - redirect location for 3xx, or `partial` for 200 to cut body in the middle
+fn serve(responses: Vec<(u16, &'static str)>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    thread::spawn(move || {
+        let body = fs::read("tests/docs/e2.xml").unwrap();
+
+        for (stream, (code, location)) in listener.incoming().zip(responses) {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim_end().is_empty() {
+                    break;
+                }
+            }
+
+            match code {
+                200 => {
+                    write!(stream,
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                        body.len()).unwrap();
+                    let size = if location == "partial" { body.len() / 2 } else { body.len() };
+                    stream.write_all(&body[.. size]).unwrap();
+                }
+                _ => {
+                    write!(stream,
+                        "HTTP/1.1 {} Status\r\nLocation: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                        code, location).unwrap();
+                }
+            };
+        }
+    });
+
+    format!("http://{}/e2.xml", addr)
+}
+
+#[test]
+fn test_http_retry() {
+    let url = serve(vec![(503, ""), (500, ""), (302, "/guide/e2.xml"), (200, "")]);
+
+    let mut source = HttpSource::default();
+    source.policy.retries = 2;
+    source.policy.backoff = Duration::from_millis(10);
+
+    let mut epg = Epg::default();
+    epg.sources.register("http", source);
+    epg.load(&url).unwrap();
+    assert_eq!(epg.channels.get("id-1").unwrap().events.len(), 2);
+
+    // retry by default
+    assert!(LoadPolicy::default().retries > 0);
+
+    // transfer error
+    let url = serve(vec![(200, "partial")]);
+
+    let mut source = HttpSource::default();
+    source.policy.retries = 0;
+
+    let mut epg = Epg::default();
+    epg.sources.register("http", source);
+    assert!(matches!(epg.load(&url), Err(EpgError::Io(_))));
+
+    let url = serve(vec![(200, "partial"), (200, "")]);
+
+    let mut source = HttpSource::default();
+    source.policy.backoff = Duration::from_millis(10);
+
+    let mut epg = Epg::default();
+    epg.sources.register("http", source);
+    epg.load(&url).unwrap();
+    assert_eq!(epg.channels.get("id-1").unwrap().events.len(), 2);
+}
+
+#[test]
+fn test_http_policy_limits() {
+    let url = serve(vec![(302, "e2.xml"), (302, "e2.xml")]);
+
+    let mut source = HttpSource::default();
+    source.policy.max_redirects = 1;
+
+    let mut epg = Epg::default();
+    epg.sources.register("http", source);
+    assert!(matches!(epg.load(&url), Err(EpgError::TooManyRedirects)));
+
+    let url = serve(vec![(200, "")]);
+
+    let mut source = HttpSource::default();
+    source.policy.max_size = Some(100);
+
+    let mut epg = Epg::default();
+    epg.sources.register("http", source);
+    assert!(matches!(epg.load(&url), Err(EpgError::TooLarge)));
+}
+
+/// Request line, headers and body received by upload stand-in
+type Upload = (String, Vec<String>, Vec<u8>);
+
+/// HTTP stand-in for upload
+fn serve_upload() -> (String, mpsc::Receiver<Upload>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+        let mut request = String::new();
+        reader.read_line(&mut request).unwrap();
+
+        let mut headers = Vec::new();
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let line = line.trim_end().to_lowercase();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(v) = line.strip_prefix("content-length:") {
+                length = v.trim().parse().unwrap();
+            }
+            headers.push(line);
+        }
+
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+
+        stream.write_all(b"HTTP/1.1 201 Created\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").unwrap();
+        tx.send((request.trim_end().to_owned(), headers, body)).unwrap();
+    });
+
+    (format!("http://{}/upload/e2.xml", addr), rx)
+}
+
+#[test]
+fn test_http_upload() {
+    let mut epg = Epg::default();
+    epg.load("tests/docs/e2.xml").unwrap();
+
+    let (url, rx) = serve_upload();
+    epg.save(&url).unwrap();
+
+    let (request, headers, body) = rx.recv().unwrap();
+    assert_eq!(request, "PUT /upload/e2.xml HTTP/1.1");
+    assert!(! headers.iter().any(|v| v.starts_with("content-encoding:")));
+    assert!(headers.iter().any(|v| v == "content-type: application/xml"));
+    assert!(body.starts_with(b"<?xml"));
+
+    let mut copy = Epg::default();
+    copy.read(&mut body.as_slice()).unwrap();
+    assert_eq!(copy.channels.get("id-1").unwrap().events, epg.channels.get("id-1").unwrap().events);
+
+    let (url, rx) = serve_upload();
+    let options = WriteOptions {
+        method: UploadMethod::Post,
+        ..WriteOptions::from_path(&url)
+    };
+    epg.save_with(&url, &options).unwrap();
+
+    let (request, _, _) = rx.recv().unwrap();
+    assert!(request.starts_with("POST "));
+
+    // gzip sent with content-encoding
+    let (url, rx) = serve_upload();
+    let options = WriteOptions {
+        compression: Compression::Gzip,
+        ..WriteOptions::from_path(&url)
+    };
+    epg.save_with(&url, &options).unwrap();
+
+    let (_, headers, body) = rx.recv().unwrap();
+    assert!(headers.iter().any(|v| v == "content-type: application/xml"));
+    assert!(headers.iter().any(|v| v == "content-encoding: gzip"));
+    assert!(body.starts_with(&[0x1f, 0x8b]));
+
+    let mut copy = Epg::default();
+    copy.read(&mut body.as_slice()).unwrap();
+    assert_eq!(copy.channels.get("id-1").unwrap().events, epg.channels.get("id-1").unwrap().events);
+}
+
+#[test]
+fn test_http_upload_policy() {
+    let mut epg = Epg::default();
+    epg.load("tests/docs/e2.xml").unwrap();
+
+    // closed port: no retries with policy from the options
+    let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    let url = format!("http://{}/upload/e2.xml", addr);
+    let mut options = WriteOptions::from_path(&url);
+    options.policy.retries = 0;
+    options.policy.backoff = Duration::from_secs(60);
+
+    let now = Instant::now();
+    assert!(epg.save_with(&url, &options).is_err());
+    assert!(now.elapsed() < Duration::from_secs(30));
+}
+
+/// HTTP stand-in for upload. Responds with server error, returns request counter
+fn serve_upload_error() -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let counter = Arc::new(AtomicUsize::new(0));
+    let requests = counter.clone();
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end().to_lowercase();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some(v) = line.strip_prefix("content-length:") {
+                    length = v.trim().parse().unwrap();
+                }
+            }
+
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            requests.fetch_add(1, Ordering::SeqCst);
+            stream.write_all(b"HTTP/1.1 500 Error\r\nContent-Length: 0\r\nConnection: close\r\n\r\n").unwrap();
+        }
+    });
+
+    (format!("http://{}/upload/e2.xml", addr), counter)
+}
+
+#[test]
+fn test_http_upload_retry() {
+    let mut epg = Epg::default();
+    epg.load("tests/docs/e2.xml").unwrap();
+
+    // PUT retried on server error
+    let (url, counter) = serve_upload_error();
+    let mut options = WriteOptions::from_path(&url);
+    options.policy.retries = 2;
+    options.policy.backoff = Duration::from_millis(10);
+    assert!(matches!(epg.save_with(&url, &options), Err(EpgError::HttpStatus(500))));
+    assert_eq!(counter.load(Ordering::SeqCst), 3);
+
+    // POST sent once
+    let (url, counter) = serve_upload_error();
+    options.method = UploadMethod::Post;
+    assert!(matches!(epg.save_with(&url, &options), Err(EpgError::HttpStatus(500))));
+    assert_eq!(counter.load(Ordering::SeqCst), 1);
+}
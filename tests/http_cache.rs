@@ -0,0 +1,84 @@
+extern crate epg;
+
+use epg::*;
+
+use std::{
+    fs,
+    thread,
+    io::{
+        BufRead,
+        BufReader,
+        Write,
+    },
+    net::TcpListener,
+};
+
+/// HTTP stand-in: serves tests/docs/e2.xml with ETag, handles If-None-Match
+fn serve(requests: usize) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    thread::spawn(move || {
+        let body = fs::read("tests/docs/e2.xml").unwrap();
+
+        for stream in listener.incoming().take(requests) {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut not_modified = false;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end().to_lowercase();
+                if line.is_empty() {
+                    break;
+                }
+                if line == "if-none-match: \"v1\"" {
+                    not_modified = true;
+                }
+            }
+
+            if not_modified {
+                stream.write_all(b"HTTP/1.1 304 Not Modified\r\nConnection: close\r\n\r\n").unwrap();
+            } else {
+                write!(stream,
+                    "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()).unwrap();
+                stream.write_all(&body).unwrap();
+            }
+        }
+    });
+
+    format!("http://{}/e2.xml", addr)
+}
+
+#[test]
+fn test_http_cache() {
+    let url = serve(3);
+
+    let dir = std::env::temp_dir().join("epg-test-http-cache");
+    let cache = HttpCache::new(&dir);
+    cache.remove(&url).unwrap();
+
+    let mut epg = Epg::default();
+    epg.sources.register_http(cache.clone());
+
+    assert!(epg.load_modified(&url).unwrap());
+    assert_eq!(epg.channels.get("id-1").unwrap().events.len(), 2);
+
+    // not modified: guide left unchanged
+    assert!(! epg.load_modified(&url).unwrap());
+
+    // not modified: cached copy loaded
+    let mut epg = Epg::default();
+    epg.sources.register_http(cache.clone());
+    epg.load(&url).unwrap();
+    assert_eq!(epg.channels.get("id-1").unwrap().events.len(), 2);
+
+    // restart with cached copy
+    let mut epg = Epg::default();
+    epg.read(&mut cache.cached(&url).unwrap()).unwrap();
+    assert_eq!(epg.channels.get("id-1").unwrap().events.len(), 2);
+
+    cache.remove(&url).unwrap();
+}
@@ -0,0 +1,102 @@
+#![cfg(feature = "json")]
+
+extern crate epg;
+
+use epg::*;
+
+use std::str;
+
+#[test]
+fn test_json() {
+    let mut epg = Epg::default();
+    epg.load("file://tests/docs/e2.xml").unwrap();
+    epg.channels.get_mut("id-1").unwrap().events[0].parental_rating.insert(*b"POL", 12);
+
+    let mut dst: Vec<u8> = Vec::new();
+    epg.write_format(&mut dst, EpgFormat::Json).unwrap();
+
+    let json = str::from_utf8(&dst).unwrap();
+    assert!(json.contains(r#""start":"2008-07-15T06:30:00Z","stop":"2008-07-15T07:00:00Z""#));
+    assert!(json.contains(r#""parental_rating":{"POL":12}"#));
+    assert!(! json.contains(r#""subtitle""#));
+
+    let mut copy = Epg::default();
+    copy.read(&mut dst.as_slice()).unwrap();
+
+    let a = epg.channels.get("id-1").unwrap();
+    let b = copy.channels.get("id-1").unwrap();
+    assert_eq!(a.name, b.name);
+    assert_eq!(a.events, b.events);
+}
+
+#[test]
+fn test_json_without_stop() {
+    let mut epg = Epg::default();
+    let mut channel = EpgChannel::default();
+    channel.events.push(EpgEvent { start: 1216103400, ..Default::default() });
+    epg.channels.insert("id-1".to_owned(), channel);
+
+    let mut dst: Vec<u8> = Vec::new();
+    epg.write_format(&mut dst, EpgFormat::Json).unwrap();
+    assert!(! str::from_utf8(&dst).unwrap().contains(r#""stop""#));
+
+    let mut copy = Epg::default();
+    copy.read(&mut dst.as_slice()).unwrap();
+    assert_eq!(copy.channels.get("id-1").unwrap().events[0].stop, None);
+
+    // date out of range
+    epg.channels.get_mut("id-1").unwrap().events[0].stop = Some(i64::MAX);
+    assert!(epg.write_format(&mut Vec::new(), EpgFormat::Json).is_err());
+}
+
+#[test]
+fn test_json_load() {
+    let mut epg = Epg::default();
+    epg.options.map.insert("id-1".to_string(), "100".to_string());
+    epg.load("file://tests/docs/e9.json").unwrap();
+
+    let channel = epg.channels.get("100").unwrap();
+    assert_eq!(channel.name.get("eng").unwrap(), "Test");
+    assert_eq!(channel.events.len(), 2);
+    assert_eq!(channel.events[0].title.get("eng").unwrap(), "Title #1");
+    assert_eq!(channel.events[0].start, 1216103400);
+
+    let mut epg = Epg::default();
+    epg.options.strict = true;
+    let json = r#"{"channels":[{"id":"id-1","events":[{"start":"2008-07-15"}]}]}"#;
+    assert!(epg.read(&mut json.as_bytes()).is_err());
+}
+
+fn reader_error(result: Result<(), EpgError>) -> JsonReaderError {
+    match result {
+        Err(EpgError::Json(e)) => e,
+        _ => panic!("JsonReaderError expected"),
+    }
+}
+
+#[test]
+fn test_json_strict() {
+    let mut epg = Epg::default();
+    epg.options.strict = true;
+
+    // event without stop time accepted like in XMLTV
+    let json = r#"{"channels":[{"id":"id-1","events":[{"start":"2008-07-15T06:30:00Z"}]}]}"#;
+    epg.read(&mut json.as_bytes()).unwrap();
+    assert_eq!(epg.channels.get("id-1").unwrap().events[0].start, 1216103400);
+
+    let json = r#"{"channels":[{"id":"id-2","events":[{"stop":"2008-07-15T06:30:00Z"}]}]}"#;
+    let e = reader_error(epg.read(&mut json.as_bytes()));
+    assert!(matches!(e, JsonReaderError::MissingField("event", "start")));
+
+    let json = r#"{"channels":[{"events":[]}]}"#;
+    let e = reader_error(epg.read(&mut json.as_bytes()));
+    assert!(matches!(e, JsonReaderError::MissingField("channel", "id")));
+
+    let json = r#"{"channels":[{"id":"id-2","events":[{"start":"2008-07-15"}]}]}"#;
+    let e = reader_error(epg.read(&mut json.as_bytes()));
+    assert!(matches!(e, JsonReaderError::InvalidDate(_)));
+
+    let json = r#"{"channels":[{"id":"id-2"}]"#;
+    let e = reader_error(epg.read(&mut json.as_bytes()));
+    assert!(matches!(e, JsonReaderError::Json(_)));
+}
@@ -0,0 +1,71 @@
+extern crate epg;
+
+use epg::*;
+
+fn title(event: Option<&EpgEvent>) -> Option<&str> {
+    event.map(|v| v.title.get("eng").unwrap().as_str())
+}
+
+#[test]
+fn test_query() {
+    let mut epg = Epg::default();
+    epg.load("file://tests/docs/e3-2.xml").unwrap();
+
+    // e3-2.xml: 06:00, 07:00, 07:30, 08:00 .. 08:30 (+0300) 2018-08-20
+    let t0600 = 1534734000;
+    let t0700 = 1534737600;
+    let t0730 = 1534739400;
+    let t0830 = 1534743000;
+
+    assert_eq!(title(epg.event_at("id-1", t0600 - 1)), None);
+    assert_eq!(title(epg.event_at("id-1", t0600)), Some("Title #1"));
+    assert_eq!(title(epg.event_at("id-1", t0700 - 1)), Some("Title #1"));
+    assert_eq!(title(epg.event_at("id-1", t0700)), Some("Title #2"));
+    assert_eq!(title(epg.event_at("id-1", t0830)), None);
+    assert_eq!(title(epg.event_at("id-2", t0700)), None);
+
+    let (now, next) = epg.now_next("id-1", t0700 + 60);
+    assert_eq!(title(now), Some("Title #2"));
+    assert_eq!(title(next), Some("Title #3"));
+
+    let (now, next) = epg.now_next("id-1", t0600 - 60);
+    assert_eq!(title(now), None);
+    assert_eq!(title(next), Some("Title #1"));
+
+    let list = epg.events_range("id-1", &((t0700 - 60) .. t0730));
+    assert_eq!(list.len(), 2);
+    assert_eq!(title(list.first()), Some("Title #1"));
+    assert_eq!(title(list.last()), Some("Title #2"));
+
+    assert_eq!(epg.events_range("id-1", &(t0830 .. t0830 + 60)).len(), 0);
+    assert_eq!(epg.events_range("id-1", &(0 .. i64::MAX)).len(), 4);
+
+    // agrees with EpgEvent::is_overlap
+    let range = (t0700 - 60) .. t0830;
+    let list = epg.events_range("id-1", &range);
+    let expected: Vec<&EpgEvent> = epg.channels.get("id-1").unwrap().events.iter()
+        .filter(|v| v.is_overlap(&range))
+        .collect();
+    assert_eq!(list.iter().collect::<Vec<_>>(), expected);
+
+    let all = epg.now_next_all(t0730);
+    let (now, next) = all.get("id-1").unwrap();
+    assert_eq!(title(*now), Some("Title #3"));
+    assert_eq!(title(*next), Some("Title #4"));
+}
+
+#[test]
+fn test_range_without_stop() {
+    let mut channel = EpgChannel::default();
+    channel.events.push(EpgEvent { start: 1000, stop: Some(2000), ..Default::default() });
+    channel.events.push(EpgEvent { start: 2000, ..Default::default() });
+    channel.sort();
+
+    // last event without stop time started at the range start
+    let list = channel.events_range(&(2000 .. 3000));
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0].start, 2000);
+
+    assert_eq!(channel.events_range(&(1500 .. 2500)).len(), 2);
+    assert_eq!(channel.events_range(&(2000 .. 2000)).len(), 0);
+}
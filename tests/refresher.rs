@@ -0,0 +1,81 @@
+extern crate epg;
+
+use epg::*;
+
+use std::{
+    thread,
+    time::{
+        Duration,
+        Instant,
+    },
+    sync::{
+        Arc,
+        atomic::{
+            AtomicUsize,
+            Ordering,
+        },
+    },
+};
+
+#[test]
+fn test_refresh() {
+    let mut aggregator = EpgAggregator::default();
+    aggregator.add("file://tests/docs/e3-1.xml", 0, EpgOptions::default());
+
+    let mut refresher = EpgRefresher::new(aggregator, Duration::from_secs(60));
+    let handle = refresher.handle();
+    assert!(handle.get().channels.is_empty());
+
+    let failures = Arc::new(AtomicUsize::new(0));
+    let counter = failures.clone();
+    refresher.on_failure(move |_| { counter.fetch_add(1, Ordering::SeqCst); });
+
+    refresher.refresh().unwrap();
+    let epg = handle.get();
+    assert_eq!(epg.channels.get("id-1").unwrap().events.len(), 2);
+
+    // last good guide stays on failure
+    refresher.aggregator.sources[0].url = "file://tests/docs/not-found.xml".to_string();
+    assert!(refresher.refresh().is_err());
+    assert_eq!(failures.load(Ordering::SeqCst), 1);
+    assert_eq!(handle.get().channels.get("id-1").unwrap().events.len(), 2);
+
+    // validation
+    refresher.aggregator.sources[0].url = "file://tests/docs/e3-2.xml".to_string();
+    refresher.validate(|epg| match epg.channels.get("id-2") {
+        Some(_) => Ok(()),
+        None => Err("id-2 not found".to_string()),
+    });
+    assert!(matches!(refresher.refresh(), Err(RefreshError::Invalid(_))));
+    assert_eq!(failures.load(Ordering::SeqCst), 2);
+    assert!(Arc::ptr_eq(&epg, &handle.get()));
+}
+
+#[test]
+fn test_refresh_thread() {
+    let mut aggregator = EpgAggregator::default();
+    aggregator.add("file://tests/docs/e3-2.xml", 0, EpgOptions::default());
+
+    let mut refresher = EpgRefresher::new(aggregator, Duration::from_millis(10));
+
+    // callback called after guide published
+    let success = Arc::new(AtomicUsize::new(0));
+    let counter = success.clone();
+    let handle = refresher.handle();
+    refresher.on_success(move |epg| {
+        if std::ptr::eq(epg, &*handle.get()) {
+            counter.fetch_add(1, Ordering::SeqCst);
+        }
+    });
+
+    let thread = refresher.start();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while success.load(Ordering::SeqCst) < 2 {
+        assert!(Instant::now() < deadline, "refresh timeout");
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    let epg = thread.handle().get();
+    assert_eq!(epg.channels.get("id-1").unwrap().events.len(), 4);
+    thread.stop();
+}
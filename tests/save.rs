@@ -0,0 +1,85 @@
+extern crate epg;
+
+use epg::*;
+
+use std::fs;
+
+#[test]
+fn test_save() {
+    let mut epg = Epg::default();
+    epg.load("file://tests/docs/e3-2.xml").unwrap();
+
+    let dir = std::env::temp_dir();
+
+    let mut list = vec!["epg-test-save.xml", "epg-test-save.xml.gz"];
+    if cfg!(feature = "xz2") { list.push("epg-test-save.xml.xz"); }
+    if cfg!(feature = "bzip2") { list.push("epg-test-save.xml.bz2"); }
+    if cfg!(feature = "zstd") { list.push("epg-test-save.xml.zst"); }
+    if cfg!(feature = "json") { list.push("epg-test-save.json.gz"); }
+
+    for name in list {
+        let path = dir.join(name);
+        let path = path.to_str().unwrap();
+        epg.save(path).unwrap();
+
+        let data = fs::read(path).unwrap();
+        match WriteOptions::from_path(path).compression {
+            Compression::None => assert!(data.starts_with(b"<?xml")),
+            Compression::Gzip => assert!(data.starts_with(&[0x1f, 0x8b])),
+            _ => {},
+        };
+
+        let mut copy = Epg::default();
+        copy.load(path).unwrap();
+        assert_eq!(copy.channels.get("id-1").unwrap().events, epg.channels.get("id-1").unwrap().events);
+
+        fs::remove_file(path).unwrap();
+        assert!(fs::metadata(format!("{}.tmp", path)).is_err());
+    }
+
+    let path = dir.join("epg-test-save-url.xml");
+    let path = path.to_str().unwrap();
+    epg.save(format!("file://{}", path)).unwrap();
+    assert!(fs::read(path).unwrap().starts_with(b"<?xml"));
+    fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_write_gzip_level() {
+    let mut epg = Epg::default();
+    epg.load("file://tests/docs/e4.xml").unwrap();
+
+    let mut options = WriteOptions {
+        compression: Compression::Gzip,
+        ..Default::default()
+    };
+
+    options.level = Some(0);
+    let fast = epg.write_with(Vec::new(), &options).unwrap();
+    options.level = Some(6);
+    let best = epg.write_with(Vec::new(), &options).unwrap();
+    assert!(best.len() < fast.len());
+
+    // gzip levels above 6 clamped to 6
+    options.level = Some(9);
+    assert_eq!(epg.write_with(Vec::new(), &options).unwrap(), best);
+
+    // out of range level clamped
+    options.level = Some(100);
+    let data = epg.write_with(Vec::new(), &options).unwrap();
+    assert_eq!(data.len(), best.len());
+
+    if cfg!(feature = "xz2") {
+        options.compression = Compression::Xz;
+        epg.write_with(Vec::new(), &options).unwrap();
+    }
+    if cfg!(feature = "zstd") {
+        options.compression = Compression::Zstd;
+        epg.write_with(Vec::new(), &options).unwrap();
+    }
+
+    options.compression = Compression::Zip;
+    assert!(matches!(
+        epg.write_with(Vec::new(), &options),
+        Err(EpgError::UnsupportedCompression(_))));
+}
@@ -0,0 +1,32 @@
+#![cfg(feature = "search")]
+
+extern crate epg;
+
+use epg::*;
+
+#[test]
+fn test_search() {
+    let mut epg = Epg::default();
+    epg.load("file://tests/docs/e8.xml").unwrap();
+
+    let index = EpgIndex::new(&epg);
+
+    let result = index.search("swiat wedlug KIEPSKICH", &SearchFilter::default());
+    let found: Vec<(&str, i64)> = result.iter().map(|(id, event)| (*id, event.start)).collect();
+    assert_eq!(found, vec![
+        ("id-1", 1534734000),
+        ("id-2", 1534734000),
+        ("id-2", 1534735800),
+    ]);
+
+    let mut filter = SearchFilter::default();
+    filter.channels.insert("id-2".to_string());
+    filter.range = Some(1534735800 .. 1534737600);
+    let result = index.search("Świat", &filter);
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].1.subtitle.get("pol").unwrap(), "Odcinek 2");
+
+    assert_eq!(index.search("informacyjny", &SearchFilter::default()).len(), 1);
+    assert!(index.search("swiat news weather", &SearchFilter::default()).is_empty());
+    assert!(index.search("", &SearchFilter::default()).is_empty());
+}
@@ -0,0 +1,29 @@
+#![cfg(feature = "serde")]
+
+extern crate epg;
+extern crate serde_json;
+
+use epg::*;
+
+#[test]
+fn test_serde_json() {
+    let mut epg = Epg::default();
+    epg.load("file://tests/docs/e2.xml").unwrap();
+
+    let channel = epg.channels.get_mut("id-1").unwrap();
+    channel.events[0].parental_rating.insert(*b"POL", 12);
+
+    let json = serde_json::to_string(&epg).unwrap();
+    assert!(json.contains(r#""parental_rating":{"POL":12}"#));
+    assert!(!json.contains("last_event_start"));
+
+    let copy: Epg = serde_json::from_str(&json).unwrap();
+    let a = epg.channels.get("id-1").unwrap();
+    let b = copy.channels.get("id-1").unwrap();
+    assert_eq!(a.name, b.name);
+    assert_eq!(a.events, b.events);
+    assert_eq!(a.last_event_start, b.last_event_start);
+
+    let json = r#"{"channels":{"id-1":{"events":[{"start":100,"parental_rating":{"POLAND":12}}]}}}"#;
+    assert!(serde_json::from_str::<Epg>(json).is_err());
+}
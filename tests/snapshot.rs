@@ -0,0 +1,51 @@
+extern crate epg;
+
+use epg::*;
+
+#[test]
+fn test_snapshot() {
+    let mut epg = Epg::default();
+    epg.load("file://tests/docs/e3-2.xml").unwrap();
+    epg.load("file://tests/docs/e4.xml").unwrap();
+    epg.channels.get_mut("id-1").unwrap().events[0].parental_rating.insert(*b"POL", 12);
+    epg.channels.get_mut("id-1").unwrap().events[1].stop = None;
+
+    let mut dst: Vec<u8> = Vec::new();
+    epg.write_snapshot(&mut dst).unwrap();
+
+    let mut copy = Epg::default();
+    copy.read_snapshot(&mut dst.as_slice()).unwrap();
+
+    let a = epg.channels.get("id-1").unwrap();
+    let b = copy.channels.get("id-1").unwrap();
+    assert_eq!(a.name, b.name);
+    assert_eq!(a.events, b.events);
+    assert_eq!(a.last_event_start, b.last_event_start);
+
+    // checksum
+    let mut broken = dst.clone();
+    let n = broken.len() - 10;
+    broken[n] ^= 0xFF;
+    assert!(matches!(
+        copy.read_snapshot(&mut broken.as_slice()),
+        Err(EpgError::Snapshot(SnapshotError::Checksum))));
+
+    // version
+    let mut broken = dst.clone();
+    broken[4 .. 6].copy_from_slice(&(SNAPSHOT_VERSION + 1).to_be_bytes());
+    assert!(matches!(
+        copy.read_snapshot(&mut broken.as_slice()),
+        Err(EpgError::Snapshot(SnapshotError::Version(_)))));
+
+    // truncated
+    assert!(matches!(
+        copy.read_snapshot(&mut &dst[.. dst.len() - 1]),
+        Err(EpgError::Snapshot(SnapshotError::Format))));
+
+    // payload size overflow
+    let mut broken = dst.clone();
+    broken[6 .. 14].copy_from_slice(&u64::MAX.to_be_bytes());
+    assert!(matches!(
+        copy.read_snapshot(&mut broken.as_slice()),
+        Err(EpgError::Snapshot(SnapshotError::Format))));
+}
@@ -0,0 +1,66 @@
+extern crate epg;
+
+use epg::*;
+
+#[test]
+fn test_timeshift() {
+    let mut epg = Epg::default();
+    epg.load("file://tests/docs/e3-1.xml").unwrap();
+
+    let id = epg.add_timeshift("id-1", 3600, "+1");
+    assert_eq!(id, "id-1+1");
+
+    let parent = epg.channels.get("id-1").unwrap();
+    let channel = epg.channel("id-1+1").unwrap();
+    assert_eq!(channel.events.len(), parent.events.len());
+    assert_eq!(channel.events[0].start, parent.events[0].start + 3600);
+    assert_eq!(channel.last_event_start, parent.last_event_start + 3600);
+
+    let name = parent.name.get("eng").unwrap();
+    assert_eq!(channel.name.get("eng").unwrap(), &format!("{} +1", name));
+
+    // parent reload updates virtual channel
+    let count = parent.events.len();
+    epg.load("file://tests/docs/e3-2.xml").unwrap();
+    let parent = epg.channels.get("id-1").unwrap();
+    assert!(parent.events.len() > count);
+    assert_eq!(epg.channel("id-1+1").unwrap().events.len(), parent.events.len());
+    assert!(epg.event_at("id-1+1", parent.events[0].start + 3600).is_some());
+
+    // virtual channel not written, not saved in snapshot
+    assert!(! epg.channels.contains_key("id-1+1"));
+    let mut xml = Vec::new();
+    epg.write(&mut xml).unwrap();
+    assert!(! String::from_utf8(xml).unwrap().contains("id-1+1"));
+
+    let mut snapshot = Vec::new();
+    epg.write_snapshot(&mut snapshot).unwrap();
+    let mut restored = Epg::default();
+    restored.read_snapshot(&mut snapshot.as_slice()).unwrap();
+    assert!(restored.channel("id-1+1").is_none());
+
+    // prune rebuilds virtual channel from parent
+    let start = epg.channels.get("id-1").unwrap().events[1].start;
+    epg.prune(&(start .. i64::MAX));
+    let parent = epg.channels.get("id-1").unwrap();
+    let channel = epg.channel("id-1+1").unwrap();
+    assert_eq!(channel.events.len(), parent.events.len());
+    assert_eq!(channel.events[0].start, start + 3600);
+
+    // parent removed
+    epg.channels.remove("id-1");
+    epg.update_timeshifts();
+    assert!(epg.channel("id-1+1").is_none());
+
+    // aggregated guide
+    let mut aggregator = EpgAggregator::default();
+    aggregator.add("file://tests/docs/e3-1.xml", 0, EpgOptions::default());
+    aggregator.timeshifts.insert("id-1+2".to_owned(), Timeshift::new("id-1", 7200));
+
+    let result = aggregator.load();
+    let parent = result.epg.channels.get("id-1").unwrap();
+    let channel = result.epg.channel("id-1+2").unwrap();
+    assert_eq!(channel.events[0].start, parent.events[0].start + 7200);
+    assert_eq!(channel.name, parent.name);
+    assert_eq!(result.origin.get("id-1+2"), result.origin.get("id-1"));
+}
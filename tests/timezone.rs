@@ -0,0 +1,75 @@
+#![cfg(feature = "tz")]
+
+extern crate epg;
+extern crate chrono_tz;
+
+use epg::*;
+
+use chrono_tz::Europe::Warsaw;
+
+// e10.xml: local times in Europe/Warsaw
+// 2019-03-31 02:00 CET clocks go forward, 02:30 does not exist
+// 2019-10-27 03:00 CEST clocks go back, 02:30 occurs twice
+const T0331_0100: i64 = 1553990400; // 00:00 UTC
+const T0331_0230_CEST: i64 = 1553992200; // 00:30 UTC
+const T0331_0230_CET: i64 = 1553995800; // 01:30 UTC
+const T1027_0230_CEST: i64 = 1572136200; // 00:30 UTC
+const T1027_0230_CET: i64 = 1572139800; // 01:30 UTC
+
+fn starts(epg: &Epg) -> Vec<i64> {
+    epg.channels.get("id-1").unwrap().events.iter().map(|e| e.start).collect()
+}
+
+#[test]
+fn test_local_time() {
+    // default UTC
+    let mut epg = Epg::default();
+    epg.load("tests/docs/e10.xml").unwrap();
+    assert_eq!(starts(&epg)[0], T0331_0100 + 3600);
+    assert!(epg.diagnostics.is_empty());
+
+    let mut epg = Epg::default();
+    epg.options.timezone = Some(Warsaw);
+    epg.load("tests/docs/e10.xml").unwrap();
+    assert_eq!(starts(&epg)[0], T0331_0100);
+    assert_eq!(starts(&epg)[1], T0331_0230_CEST);
+    assert_eq!(starts(&epg)[3], T1027_0230_CEST);
+
+    assert_eq!(epg.diagnostics.len(), 4);
+    assert_eq!(epg.diagnostics[0].issue, LocalTimeIssue::NonExistent);
+    assert_eq!(epg.diagnostics[0].value, "20190331023000");
+    assert_eq!(epg.diagnostics[0].resolved, Some(T0331_0230_CEST));
+    assert_eq!(epg.diagnostics[2].issue, LocalTimeIssue::Ambiguous);
+
+    // diagnostics cleared on reload
+    epg.load("tests/docs/e10.xml").unwrap();
+    assert_eq!(epg.diagnostics.len(), 4);
+
+    let mut epg = Epg::default();
+    epg.options.channel_timezone.insert("id-1".to_owned(), Warsaw);
+    epg.options.dst_policy = DstPolicy::Later;
+    epg.load("tests/docs/e10.xml").unwrap();
+    assert_eq!(starts(&epg)[1], T0331_0230_CET);
+    assert_eq!(starts(&epg)[3], T1027_0230_CET);
+
+    // rejected in the strict mode
+    let mut epg = Epg::default();
+    epg.options.timezone = Some(Warsaw);
+    epg.options.dst_policy = DstPolicy::Reject;
+    epg.options.strict = true;
+    assert!(epg.load("tests/docs/e10.xml").is_err());
+    assert_eq!(epg.diagnostics[0].resolved, None);
+}
+
+#[test]
+fn test_local_time_skipped_channel() {
+    // denied channel not reported
+    let mut epg = Epg::default();
+    epg.options.timezone = Some(Warsaw);
+    epg.options.dst_policy = DstPolicy::Reject;
+    epg.options.strict = true;
+    epg.options.deny.insert("id-1".to_owned());
+    epg.load("tests/docs/e10.xml").unwrap();
+    assert!(epg.diagnostics.is_empty());
+    assert!(! epg.channels.contains_key("id-1"));
+}
@@ -1,3 +1,6 @@
+// baseline tests kept as written
+#![allow(clippy::get_first, clippy::field_reassign_with_default)]
+
 extern crate epg;
 extern crate mpegts;
 
@@ -6,7 +9,10 @@ use epg::*;
 use mpegts::psi::*;
 use mpegts::textcode::*;
 
-use std::str;
+use std::{
+    convert::TryFrom,
+    str,
+};
 
 #[test]
 fn test_parse_programme() {
@@ -17,7 +23,7 @@ fn test_parse_programme() {
 
         // check event
         assert_eq!(p.start, 1216103400);
-        assert_eq!(p.stop, 1216060200);
+        assert_eq!(p.stop, Some(1216060200));
         assert_eq!(p.title.get("eng").unwrap(), "Title");
         assert_eq!(p.desc.get("eng").unwrap(), "Desc");
     }
@@ -71,9 +77,9 @@ fn test_merge_xmltv() {
     let ev2 = channel.events.get(2).unwrap();
     let ev3 = channel.events.get(3).unwrap();
 
-    assert_eq!(ev0.stop, ev1.start);
-    assert_eq!(ev1.stop, ev2.start);
-    assert_eq!(ev2.stop, ev3.start);
+    assert_eq!(ev0.stop, Some(ev1.start));
+    assert_eq!(ev1.stop, Some(ev2.start));
+    assert_eq!(ev2.stop, Some(ev3.start));
 }
 
 #[test]
@@ -91,7 +97,278 @@ fn test_convert_to_psi() {
     eit.pnr = 100;
     eit.tsid = 1;
     eit.onid = 1;
-    eit.items.push(EitItem::from(&*event));
+    eit.items.extend(EitItem::try_from(&*event));
 
     // TODO: more tests
 }
+
+#[test]
+fn test_convert_long_event() {
+    let mut epg = Epg::default();
+    epg.load("file://tests/docs/e4.xml").unwrap();
+
+    // e4.xml: events longer than EIT duration limit
+    let event = epg.channels.get("id-1").unwrap().events.get(0).unwrap();
+    assert!(matches!(EitItem::try_from(event), Err(EitError::DurationOverflow(_))));
+
+    // split into parts fit into EIT
+    epg.split_events(EIT_MAX_DURATION);
+    let channel = epg.channels.get("id-1").unwrap();
+    assert!(channel.events.len() > 2);
+    for event in &channel.events {
+        let item = EitItem::try_from(event).unwrap();
+        assert!(i64::from(item.duration) <= EIT_MAX_DURATION);
+    }
+}
+
+#[test]
+fn test_generate_psi() {
+    let mut epg = Epg::default();
+    epg.load("file://tests/docs/e4.xml").unwrap();
+    // e4.xml: events longer than EIT duration limit
+    epg.split_events(EIT_MAX_DURATION);
+
+    let channel = epg.channels.get_mut("id-1").unwrap();
+    let event = channel.events.iter_mut().next().unwrap();
+    event.codepage = ISO8859_5;
+    let start = event.start;
+
+    epg.services.insert("id-1", DvbService { onid: 1, tsid: 1, pnr: 100 });
+
+    let mut generator = EitGenerator::default();
+    let pf = generator.present_following(&epg, "id-1", start).unwrap();
+    let eit = &pf[0].eit;
+    assert_eq!(eit.table_id, EIT_PF_ACTUAL);
+    assert_eq!((eit.onid, eit.tsid, eit.pnr), (1, 1, 100));
+    assert_eq!(eit.items[0].start, start as u64);
+}
+
+#[test]
+fn test_strict_xmltv() {
+    // valid document passes in the strict mode
+    for url in &["file://tests/docs/e2.xml", "file://tests/docs/e1.xml.gz"] {
+        let mut epg = Epg::default();
+        epg.options.strict = true;
+        epg.load(url).unwrap();
+        assert!(epg.channels.contains_key("id-1"));
+    }
+
+    let invalid = &[
+        // unknown root element
+        r#"<guide><channel id="id-1"></channel></guide>"#,
+        // duplicate channel
+        r#"<tv><channel id="id-1"></channel><channel id="id-1"></channel></tv>"#,
+        // undeclared channel
+        r#"<tv><programme start="20080715003000 +0000" channel="id-2"></programme></tv>"#,
+        // channel without id
+        r#"<tv><channel></channel></tv>"#,
+        // programme without start
+        r#"<tv><channel id="id-1"></channel><programme channel="id-1"></programme></tv>"#,
+        // bad date
+        r#"<tv><channel id="id-1"></channel><programme start="2008-07-15" channel="id-1"></programme></tv>"#,
+    ];
+
+    for doc in invalid {
+        // lenient mode skips broken elements
+        let mut epg = Epg::default();
+        epg.read(&mut doc.as_bytes()).unwrap();
+
+        let mut epg = Epg::default();
+        epg.options.strict = true;
+        epg.options.deny.insert("id-3".to_string());
+        assert!(epg.read(&mut doc.as_bytes()).is_err(), "{}", doc);
+    }
+
+    // skipped channel not checked
+    let doc = r#"<tv><channel id="id-3"></channel><programme start="2008-07-15" channel="id-3"></programme></tv>"#;
+    let mut epg = Epg::default();
+    epg.options.strict = true;
+    epg.options.deny.insert("id-3".to_string());
+    epg.read(&mut doc.as_bytes()).unwrap();
+}
+
+#[test]
+fn test_strict_loaded_channel() {
+    let mut epg = Epg::default();
+    epg.load("file://tests/docs/e3-1.xml").unwrap();
+    epg.options.strict = true;
+
+    // channel loaded before is not declared in the document
+    let doc = r#"<tv><programme start="20180820090000 +0300" channel="id-1"></programme></tv>"#;
+    assert!(epg.read(&mut doc.as_bytes()).is_err());
+
+    // channel declared after its programmes
+    let doc = r#"<tv>
+        <programme start="20180820090000 +0300" channel="id-1"></programme>
+        <channel id="id-1"></channel>
+    </tv>"#;
+    epg.read(&mut doc.as_bytes()).unwrap();
+}
+
+#[test]
+fn test_programme_before_channel() {
+    let mut epg = Epg::default();
+    epg.load("file://tests/docs/e5.xml").unwrap();
+
+    let channel = epg.channels.get("id-1").unwrap();
+    assert_eq!(channel.name.get("eng").unwrap(), "Test");
+    assert_eq!(channel.events.len(), 2);
+    assert_eq!(channel.events[0].title.get("eng").unwrap(), "Title #1");
+    assert_eq!(channel.events[1].title.get("eng").unwrap(), "Title #2");
+    assert!(! epg.channels.contains_key("id-2"));
+
+    let mut epg = Epg::default();
+    epg.options.keep_undeclared = true;
+    epg.load("file://tests/docs/e5.xml").unwrap();
+
+    let channel = epg.channels.get("id-2").unwrap();
+    assert!(channel.name.is_empty());
+    assert_eq!(channel.events.len(), 1);
+
+    let mut epg = Epg::default();
+    epg.options.strict = true;
+    assert!(epg.load("file://tests/docs/e5.xml").is_err());
+}
+
+#[test]
+fn test_channel_filter() {
+    let mut epg = Epg::default();
+    epg.options.allow.insert("id-1".to_string());
+    epg.options.allow.insert("id-2".to_string());
+    epg.options.deny.insert("id-2".to_string());
+    epg.options.map.insert("id-1".to_string(), "100".to_string());
+    epg.load("file://tests/docs/e6.xml").unwrap();
+
+    assert_eq!(epg.channels.len(), 1);
+
+    let channel = epg.channels.get("100").unwrap();
+    assert_eq!(channel.name.get("eng").unwrap(), "Channel #1");
+    assert_eq!(channel.events.len(), 1);
+    assert_eq!(channel.events[0].title.get("eng").unwrap(), "Title #1");
+}
+
+#[test]
+fn test_time_window() {
+    // e3-2.xml: 06:00, 07:00, 07:30, 08:00 (+0300) 2018-08-20
+    let window = 1534737600 .. 1534739400; // 07:00 .. 07:30
+
+    let mut epg = Epg::default();
+    epg.options.window = Some(window.clone());
+    epg.load("file://tests/docs/e3-2.xml").unwrap();
+
+    let channel = epg.channels.get("id-1").unwrap();
+    assert_eq!(channel.events.len(), 1);
+    assert_eq!(channel.events[0].title.get("eng").unwrap(), "Title #2");
+
+    let mut epg = Epg::default();
+    epg.load("file://tests/docs/e3-2.xml").unwrap();
+    assert_eq!(epg.channels.get("id-1").unwrap().events.len(), 4);
+
+    epg.prune(&(1534737600 .. 1534741200)); // 07:00 .. 08:00
+    let channel = epg.channels.get("id-1").unwrap();
+    assert_eq!(channel.events.len(), 2);
+    assert_eq!(channel.events[0].title.get("eng").unwrap(), "Title #2");
+    assert_eq!(channel.events[1].title.get("eng").unwrap(), "Title #3");
+    assert_eq!(channel.last_event_start, channel.events[1].start);
+}
+
+#[test]
+fn test_compressed_xmltv() {
+    let mut list = Vec::new();
+    if cfg!(feature = "xz2") { list.push("file://tests/docs/e2.xml.xz"); }
+    if cfg!(feature = "bzip2") { list.push("file://tests/docs/e2.xml.bz2"); }
+    if cfg!(feature = "zstd") { list.push("file://tests/docs/e2.xml.zst"); }
+    if cfg!(feature = "zip") { list.push("file://tests/docs/e2.xml.zip"); }
+
+    for url in list {
+        let mut epg = Epg::default();
+        epg.load(url).unwrap();
+
+        let channel = epg.channels.get("id-1").unwrap();
+        assert_eq!(channel.events.len(), 2);
+        assert_eq!(channel.events[0].title.get("eng").unwrap(), "Title #1");
+    }
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn test_zip_entry() {
+    let mut epg = Epg::default();
+    epg.options.archive_entry = Some("e4.xml".to_string());
+    epg.load("file://tests/docs/e2.xml.zip").unwrap();
+
+    let channel = epg.channels.get("id-1").unwrap();
+    assert!(channel.name.contains_key("rus"));
+
+    let mut epg = Epg::default();
+    epg.options.archive_entry = Some("e0.xml".to_string());
+    assert!(epg.load("file://tests/docs/e2.xml.zip").is_err());
+}
+
+struct DataSource;
+
+impl EpgSource for DataSource {
+    fn open(&self, url: &str) -> Result<Box<dyn std::io::BufRead>, EpgError> {
+        let (_, data) = split_url(url);
+        let data = match data.find(',') {
+            Some(i) => &data[i + 1 ..],
+            None => data,
+        };
+        Ok(Box::new(std::io::Cursor::new(data.as_bytes().to_vec())))
+    }
+}
+
+#[test]
+fn test_split_url() {
+    assert_eq!(split_url("file:///tmp/e1.xml"), ("file", "/tmp/e1.xml"));
+    assert_eq!(split_url("http://example.com/e1.xml"), ("http", "example.com/e1.xml"));
+    assert_eq!(split_url("data:text/xml,<tv></tv>"), ("data", "text/xml,<tv></tv>"));
+    assert_eq!(split_url("/tmp/e1:2.xml"), ("file", "/tmp/e1:2.xml"));
+    assert_eq!(split_url("C:\\e1.xml"), ("file", "C:\\e1.xml"));
+    assert_eq!(split_url("e1.xml"), ("file", "e1.xml"));
+}
+
+#[test]
+fn test_custom_source() {
+    let url = r#"data:text/xml,<tv><channel id="id-1"><display-name lang="en">Data</display-name></channel></tv>"#;
+
+    let mut epg = Epg::default();
+    assert!(matches!(epg.load(url), Err(EpgError::UnknownSourceType)));
+
+    epg.sources.register("data", DataSource);
+    epg.load(url).unwrap();
+
+    let channel = epg.channels.get("id-1").unwrap();
+    assert_eq!(channel.name.get("eng").unwrap(), "Data");
+}
+
+#[test]
+fn test_historical_dates() {
+    let doc = r#"<tv>
+        <channel id="id-1"></channel>
+        <programme start="19691231230000 +0000" stop="19700101003000 +0000" channel="id-1"></programme>
+        <programme start="19700101003000 +0000" channel="id-1"></programme>
+    </tv>"#;
+
+    let mut epg = Epg::default();
+    epg.read(&mut doc.as_bytes()).unwrap();
+
+    let channel = epg.channels.get("id-1").unwrap();
+    assert_eq!(channel.events.len(), 2);
+    assert_eq!(channel.events[0].start, -3600);
+    assert_eq!(channel.events[0].stop, Some(1800));
+    assert_eq!(channel.last_event_start, 1800);
+    assert_eq!(epg.event_at("id-1", -1).unwrap().start, -3600);
+    assert_eq!(channel.events[1].stop, None);
+
+    // stop attribute omitted for event without stop time
+    let mut dst: Vec<u8> = Vec::new();
+    epg.write(&mut dst).unwrap();
+    let mut copy = Epg::default();
+    copy.read(&mut dst.as_slice()).unwrap();
+    assert_eq!(copy.channels.get("id-1").unwrap().events, channel.events);
+
+    // date out of range
+    epg.channels.get_mut("id-1").unwrap().events[1].stop = Some(i64::MAX);
+    assert!(matches!(epg.write(&mut Vec::new()), Err(EpgError::XmlWriter(_))));
+}